@@ -163,6 +163,7 @@ def gen_configuration():
             or line.startswith("-- ## Config ")
             or line.startswith("-- ## Function ")
             or line.startswith("-- ## On Load ")
+            or line.startswith("-- ## Plugins ")
         ):
             reading = configuration
 
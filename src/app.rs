@@ -1,13 +1,21 @@
+use crate::command_line::{self, CommandLine, CommandLineHistory};
+use crate::command_palette::{CommandPalette, COMMAND_PALETTE_MODE};
 use crate::config::Config;
 use crate::config::Hooks;
+use crate::config::KeyBindings;
 use crate::config::Mode;
+use crate::config::OutputFormat;
 pub use crate::directory_buffer::DirectoryBuffer;
 use crate::dirs;
+use crate::duplicates::Duplicates;
 use crate::explorer;
-use crate::input::{InputOperation, Key};
+use crate::hash::{ChecksumStatus, HashAlgorithm, HashOptions, NodeHash, NodeHashes};
+use crate::input::{InputOperation, Key, MouseEvent};
 use crate::lua;
 pub use crate::msg::in_::external::Command;
+pub use crate::msg::in_::external::DelayedMessages;
 pub use crate::msg::in_::external::ExplorerConfig;
+use crate::msg::in_::external::Macro;
 pub use crate::msg::in_::external::NodeFilter;
 pub use crate::msg::in_::external::NodeFilterApplicable;
 use crate::msg::in_::external::NodeSearcherApplicable;
@@ -19,14 +27,19 @@ pub use crate::msg::in_::MsgIn;
 pub use crate::msg::out::MsgOut;
 pub use crate::node::Node;
 pub use crate::node::ResolvedNode;
+use crate::permissions::{self, Ownership, Permissions};
 pub use crate::pipe::Pipe;
+use crate::plugin;
 use crate::search::SearchAlgorithm;
+use crate::socket;
 use crate::ui::Layout;
+use crate::xattr::{self, Xattr};
 use anyhow::{bail, Result};
 use gethostname::gethostname;
 use indexmap::set::IndexSet;
 use path_absolutize::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::env;
@@ -38,6 +51,8 @@ use tui_input::{Input, InputRequest};
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const TEMPLATE_TABLE_ROW: &str = "TEMPLATE_TABLE_ROW";
 pub const UNSUPPORTED_STR: &str = "???";
+pub const TABLE_PANEL: &str = "Table";
+const MAX_MACRO_PLAYBACK_KEYS: usize = 100_000;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Task {
@@ -51,6 +66,36 @@ impl Task {
     }
 }
 
+/// The last action executed from a key binding, to be repeated via
+/// `RepeatLastAction`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LastAction {
+    pub key: Key,
+    pub messages: Vec<ExternalMsg>,
+    pub input_buffer: Option<String>,
+    pub count: Option<usize>,
+}
+
+/// The panel with the keyboard focus, other than the table, and the mode it was
+/// focused in.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PanelFocus {
+    pub name: String,
+    pub mode: String,
+}
+
+/// The state of the keyboard macro being recorded.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MacroRecording {
+    pub register: String,
+    pub mode: String,
+    pub keys: Vec<String>,
+
+    /// The number of keys recorded till the last time the recording mode was
+    /// active. The keys after it, used to stop the recording, are dropped.
+    pub checkpoint: usize,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum LogLevel {
@@ -79,15 +124,25 @@ impl Log {
     }
 }
 
-impl std::fmt::Display for Log {
+impl std::fmt::Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let level_str = match self.level {
-            LogLevel::Info => "INFO   ",
-            LogLevel::Warning => "WARNING",
-            LogLevel::Success => "SUCCESS",
-            LogLevel::Error => "ERROR  ",
+        let level_str = match self {
+            Self::Info => "INFO",
+            Self::Warning => "WARNING",
+            Self::Success => "SUCCESS",
+            Self::Error => "ERROR",
         };
-        write!(f, "[{0}] {level_str} {1}", &self.created_at, &self.message)
+        f.pad(level_str)
+    }
+}
+
+impl std::fmt::Display for Log {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{0}] {1:<7} {2}",
+            &self.created_at, &self.level, &self.message
+        )
     }
 }
 
@@ -215,7 +270,7 @@ impl History {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct LuaContextHeavy {
     pub version: String,
     pub pwd: String,
@@ -232,6 +287,8 @@ pub struct LuaContextHeavy {
     pub explorer_config: ExplorerConfig,
     pub history: History,
     pub last_modes: Vec<Mode>,
+    pub count: Option<usize>,
+    pub macros: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -248,6 +305,8 @@ pub struct LuaContextLight {
     pub pid: u32,
     pub session_path: String,
     pub explorer_config: ExplorerConfig,
+    pub count: Option<usize>,
+    pub macros: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -260,7 +319,8 @@ pub struct InputBuffer {
 pub struct App {
     pub bin: String,
     pub version: String,
-    pub config: Config,
+    pub config: Box<Config>,
+    pub config_files: Vec<PathBuf>,
     pub hooks: Hooks,
     pub vroot: Option<String>,
     pub initial_vroot: Option<String>,
@@ -278,10 +338,53 @@ pub struct App {
     pub pipe: Pipe,
     pub explorer_config: ExplorerConfig,
     pub logs: Vec<Log>,
+    pub logs_dropped: usize,
     pub logs_hidden: bool,
+    pub log_level_filter: Option<LogLevel>,
     pub history: History,
     pub last_modes: Vec<Mode>,
     pub hostname: String,
+    pub duplicates: Option<Duplicates>,
+    pub hashes: HashMap<String, NodeHashes>,
+    pub hash_to_copy: Option<(String, HashAlgorithm)>,
+    pub pending_keys: Vec<Key>,
+    pub pending_keys_id: usize,
+    pub count: Option<usize>,
+    pub last_action: Option<LastAction>,
+    pub macros: BTreeMap<String, Vec<String>>,
+    pub macro_recording: Option<MacroRecording>,
+    pub macro_playback: VecDeque<Key>,
+    pub mouse: Option<MouseEvent>,
+    pub focused_panel: Option<PanelFocus>,
+    pub command_palette: Option<CommandPalette>,
+    pub command_line_history: CommandLineHistory,
+}
+
+/// The given config file, or the one in the config directory if it exists,
+/// followed by the extra config files.
+pub(crate) fn config_files(
+    config_file: Option<PathBuf>,
+    extra_config_files: Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    let config_file = if let Some(path) = config_file {
+        Some(path)
+    } else if let Some(dir) = dirs::config_dir() {
+        let path = dir.join("xplr/init.lua");
+        if path.exists() {
+            Some(path)
+        } else {
+            None
+        }
+    } else {
+        let path = PathBuf::from("/etc/xplr/init.lua");
+        if path.exists() {
+            Some(path)
+        } else {
+            None
+        }
+    };
+
+    config_file.into_iter().chain(extra_config_files).collect()
 }
 
 impl App {
@@ -296,30 +399,14 @@ impl App {
         let (mut config, hooks) = lua::init(lua)?;
         let mut hooks = hooks.unwrap_or_default();
 
-        let config_file = if let Some(path) = config_file {
-            Some(path)
-        } else if let Some(dir) = dirs::config_dir() {
-            let path = dir.join("xplr/init.lua");
-            if path.exists() {
-                Some(path)
-            } else {
-                None
-            }
-        } else {
-            let path = PathBuf::from("/etc/xplr/init.lua");
-            if path.exists() {
-                Some(path)
-            } else {
-                None
-            }
-        };
-
-        let config_files = config_file.into_iter().chain(extra_config_files);
+        let config_files = config_files(config_file, extra_config_files);
 
+        let mut loaded_config_files = vec![];
         let mut load_errs = vec![];
         for config_file in config_files {
             match lua::extend(lua, &config_file.to_string_lossy()) {
                 Ok((c, maybe_hooks)) => {
+                    loaded_config_files.push(config_file);
                     config = c;
                     if let Some(h) = maybe_hooks {
                         hooks = hooks.extend(h);
@@ -331,6 +418,17 @@ impl App {
             }
         }
 
+        for err in plugin::load_all(lua, &config.plugins) {
+            load_errs.push(err);
+        }
+        if !config.plugins.is_empty() {
+            // The plugins may have changed the config.
+            match lua::current_config(lua) {
+                Ok(c) => config = c,
+                Err(e) => load_errs.push(e.to_string()),
+            }
+        }
+
         let mode = match config.modes.get(
             &config
                 .general
@@ -378,7 +476,10 @@ impl App {
             fs::create_dir_all(&session_path)?;
         }
 
-        let mut explorer_config = ExplorerConfig::default();
+        let mut explorer_config = ExplorerConfig {
+            load_extended_metadata: config.general.load_extended_metadata,
+            ..Default::default()
+        };
         if !config.general.show_hidden {
             explorer_config.filters.replace(NodeFilterApplicable::new(
                 NodeFilter::RelativePathDoesNotStartWith,
@@ -423,7 +524,8 @@ impl App {
         let mut app = Self {
             bin,
             version: VERSION.to_string(),
-            config,
+            config: Box::new(config),
+            config_files: loaded_config_files,
             vroot,
             initial_vroot,
             pwd,
@@ -440,11 +542,27 @@ impl App {
             pipe: Pipe::from_session_path(&session_path)?,
             explorer_config,
             logs: Default::default(),
+            logs_dropped: Default::default(),
             logs_hidden: Default::default(),
+            log_level_filter: Default::default(),
             history: History::default().push(hist),
             last_modes: Default::default(),
             hostname,
             hooks,
+            duplicates: Default::default(),
+            hashes: Default::default(),
+            hash_to_copy: Default::default(),
+            pending_keys: Default::default(),
+            pending_keys_id: Default::default(),
+            count: Default::default(),
+            last_action: Default::default(),
+            macros: Default::default(),
+            macro_recording: Default::default(),
+            macro_playback: Default::default(),
+            mouse: Default::default(),
+            focused_panel: Default::default(),
+            command_palette: Default::default(),
+            command_line_history: Default::default(),
         };
 
         let has_errs = !load_errs.is_empty();
@@ -499,7 +617,16 @@ impl App {
                 self.add_last_focus(parent, focus_path)
             }
             InternalMsg::HandleKey(key) => self.handle_key(key),
+            InternalMsg::HandleMouse(evt) => self.handle_mouse(evt),
+            InternalMsg::KeySequenceTimeout(id) => self.key_sequence_timeout(id),
+            InternalMsg::PlayNextMacroKey => self.play_next_macro_key(),
             InternalMsg::RefreshSelection => self.refresh_selection(),
+            InternalMsg::SetDuplicates(dups) => self.set_duplicates(dups),
+            InternalMsg::SetNodeHashes(hashes) => self.set_node_hashes(hashes),
+            InternalMsg::HandleSocketRequest(id, req) => {
+                self.handle_socket_request(id, req)
+            }
+            InternalMsg::CallLuaTimer(id) => self.call_lua_timer(id),
         }
     }
 
@@ -571,6 +698,17 @@ impl App {
                 }
                 RemoveInputBufferLastWord => self.remove_input_buffer_last_word(),
                 ResetInputBuffer => self.reset_input_buffer(),
+                UpdateCountFromKey => self.update_count_from_key(key),
+                SetCount(n) => self.set_count(n),
+                ClearCount => self.clear_count(),
+                Repeat(n, msgs) => self.repeat(n, msgs, key),
+                RepeatLastAction => self.repeat_last_action(),
+                StartMacroRecording(reg) => self.start_macro_recording(reg),
+                StartMacroRecordingFromKey => self.start_macro_recording_from_key(key),
+                StopMacroRecording => self.stop_macro_recording(),
+                PlayMacro(reg) => self.play_macro(&reg),
+                PlayMacroFromKey => self.play_macro_from_key(key),
+                SetMacro(m) => self.set_macro(m),
                 SwitchMode(mode) => self.switch_mode(&mode),
                 SwitchModeKeepingInputBuffer(mode) => {
                     self.switch_mode_keeping_input_buffer(&mode)
@@ -598,6 +736,7 @@ impl App {
                 BashExecSilently0(cmd) => self.bash_exec_silently0(cmd),
                 CallLua(func) => self.call_lua(func),
                 CallLuaSilently(func) => self.call_lua_silently(func),
+                CallLuaAsync(func) => self.call_lua_async(func),
                 LuaEval(code) => self.lua_eval(code),
                 LuaEvalSilently(code) => self.lua_eval_silently(code),
                 Select => self.select(),
@@ -654,9 +793,57 @@ impl App {
                 ToggleSearchAlgorithm => self.toggle_search_algorithm(),
                 AcceptSearch => self.accept_search(),
                 CancelSearch => self.cancel_search(),
+                FindDuplicates(p) => self.find_duplicates(&p),
+                ToggleDuplicateGroup => self.toggle_duplicate_group(),
+                SelectDuplicates => self.select_duplicates(),
+                ClearDuplicates => self.clear_duplicates(),
+                Hash(opts) => self.hash(opts),
+                CopyHash(opts) => self.copy_hash(opts),
+                VerifyChecksumFile(p) => self.verify_checksum_file(&p),
+                SetXattr(x) => self.set_xattr(x),
+                SetXattrFromInput(n) => self.set_xattr_from_input(n),
+                RemoveXattr(n) => self.remove_xattr(&n),
+                SetPermissions(p) => self.set_permissions(&p.octal, false),
+                SetPermissionsRecursive(p) => self.set_permissions(&p.octal, true),
+                SetPermissionsFromInput => self.set_permissions_from_input(false),
+                SetPermissionsFromInputRecursive => {
+                    self.set_permissions_from_input(true)
+                }
+                ChangeOwner(o) => self.change_owner(o, false),
+                ChangeOwnerRecursive(o) => self.change_owner(o, true),
                 EnableMouse => self.enable_mouse(),
                 DisableMouse => self.disable_mouse(),
                 ToggleMouse => self.toggle_mouse(),
+                FocusFromMouse => self.focus_from_mouse(),
+                ToggleSelectionFromMouse => self.toggle_selection_from_mouse(),
+                SelectRangeFromMouse => self.select_range_from_mouse(),
+                FocusPanel(name) => self.focus_panel(name),
+                UnfocusPanel => self.unfocus_panel(),
+                CyclePanelFocus => self.cycle_panel_focus(),
+                FocusNextPanelRow => self.move_panel_cursor(1),
+                FocusPreviousPanelRow => self.move_panel_cursor(-1),
+                ActivatePanelRow => self.activate_panel_row(),
+                SearchPanel(query) => self.search_panel(query),
+                SearchPanelFromInput => self.search_panel_from_input(),
+                SearchPanelNext => self.search_panel_next(),
+                SearchPanelPrevious => self.search_panel_previous(),
+                OpenCommandPalette => self.open_command_palette(),
+                SearchCommandPaletteFromInput => {
+                    self.search_command_palette_from_input()
+                }
+                FocusNextCommandPaletteItem => self.focus_next_command_palette_item(),
+                FocusPreviousCommandPaletteItem => {
+                    self.focus_previous_command_palette_item()
+                }
+                ExecuteCommandPaletteItem => self.execute_command_palette_item(),
+                CloseCommandPalette => self.close_command_palette(),
+                ExecuteCommandLine(line) => self.execute_command_line(&line),
+                ExecuteCommandLineFromInput => self.execute_command_line_from_input(),
+                CompleteCommandLine => self.complete_command_line(),
+                PreviousCommandLine => self.previous_command_line(),
+                NextCommandLine => self.next_command_line(),
+                EnqueueAfter(d) => self.enqueue_after(d),
+                CancelTimer(id) => self.cancel_timer(id),
                 StartFifo(f) => self.start_fifo(f),
                 StopFifo => self.stop_fifo(),
                 ToggleFifo(f) => self.toggle_fifo(f),
@@ -664,6 +851,10 @@ impl App {
                 LogSuccess(l) => self.log_success(l),
                 LogWarning(l) => self.log_warning(l),
                 LogError(l) => self.log_error(l),
+                SetLogLevelFilter(l) => self.set_log_level_filter(l),
+                UnsetLogLevelFilter => self.unset_log_level_filter(),
+                CopyLog(i) => self.copy_log(i),
+                CopyLogs => self.copy_logs(),
                 Quit => self.quit(),
                 PrintPwdAndQuit => self.print_pwd_and_quit(),
                 PrintFocusPathAndQuit => self.print_focus_path_and_quit(),
@@ -689,10 +880,147 @@ impl App {
     }
 
     fn handle_key(mut self, key: Key) -> Result<Self> {
+        if let Some(rec) = self.macro_recording.as_mut() {
+            if self.pending_keys.is_empty() && self.mode.name == rec.mode {
+                rec.checkpoint = rec.keys.len();
+            }
+            rec.keys.push(key.to_string());
+        }
+
+        let kb = self.key_bindings();
+
+        let mut keys = self.pending_keys.clone();
+        keys.push(key);
+        let sequence = keys
+            .iter()
+            .map(Key::to_string)
+            .collect::<Vec<String>>()
+            .join(" ");
+        let prefix = format!("{sequence} ");
+
+        let mut action = None;
+        let mut is_prefix = false;
+        for (k, a) in kb.on_key.iter() {
+            let k = k.split_whitespace().collect::<Vec<&str>>().join(" ");
+            if k == sequence {
+                action = Some(a.messages.clone());
+            } else if k.starts_with(&prefix) {
+                is_prefix = true;
+            }
+        }
+
+        if is_prefix {
+            // Wait for the next key in the sequence.
+            self.pending_keys = keys;
+            self.pending_keys_id += 1;
+            let timeout = self.config.general.key_sequence_timeout_ms;
+            if timeout > 0 {
+                self.msg_out.push_back(MsgOut::KeySequenceTimeoutAsync(
+                    self.pending_keys_id,
+                    timeout,
+                ));
+            }
+            Ok(self)
+        } else if let Some(msgs) = action {
+            self.pending_keys.clear();
+            Ok(self.enqueue_key_msgs(key, msgs))
+        } else if !self.pending_keys.is_empty() {
+            // The sequence is broken. Handle the pending keys as usual, and
+            // start over with the current key, once their messages are handled.
+            self = self.flush_pending_keys()?;
+            if let Some(rec) = self.macro_recording.as_mut() {
+                // It will be recorded again when handled.
+                rec.keys.pop();
+            }
+            let task =
+                Task::new(MsgIn::Internal(InternalMsg::HandleKey(key)), Some(key));
+            self.msg_out.push_back(MsgOut::Enqueue(task));
+            Ok(self)
+        } else {
+            let msgs = self.resolve_key(key);
+            Ok(self.enqueue_key_msgs(key, msgs))
+        }
+    }
+
+    fn handle_mouse(mut self, evt: MouseEvent) -> Result<Self> {
+        self.mouse = Some(evt);
+        let msgs = self
+            .mode
+            .key_bindings
+            .on_mouse
+            .get(&evt.action)
+            .map(|a| a.messages.clone())
+            .unwrap_or_default();
+
+        for msg in msgs {
+            let task = Task::new(MsgIn::External(msg), None);
+            self.msg_out.push_back(MsgOut::Enqueue(task));
+        }
+        Ok(self)
+    }
+
+    fn key_sequence_timeout(self, id: usize) -> Result<Self> {
+        if id == self.pending_keys_id {
+            self.flush_pending_keys()
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Handle the pending key sequence as a whole if it's bound to an action,
+    /// else handle the keys one by one, ignoring the sequences.
+    fn flush_pending_keys(mut self) -> Result<Self> {
+        let keys = std::mem::take(&mut self.pending_keys);
+        let Some(last) = keys.last().copied() else {
+            return Ok(self);
+        };
+
+        let sequence = keys
+            .iter()
+            .map(Key::to_string)
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let action = self.key_bindings().on_key.iter().find_map(|(k, a)| {
+            if k.split_whitespace().collect::<Vec<&str>>().join(" ") == sequence {
+                Some(a.messages.clone())
+            } else {
+                None
+            }
+        });
+
+        if let Some(msgs) = action {
+            self = self.enqueue_key_msgs(last, msgs);
+        } else {
+            for key in keys {
+                let msgs = self.resolve_key(key);
+                self = self.enqueue_key_msgs(key, msgs);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// The key bindings of the current mode, overridden by the panel key
+    /// bindings, if a panel was focused in this mode.
+    fn key_bindings(&self) -> KeyBindings {
         let kb = self.mode.key_bindings.clone();
+        if self
+            .focused_panel
+            .as_ref()
+            .is_some_and(|p| p.mode == self.mode.name)
+        {
+            let panel_kb = self.config.general.panel_key_bindings.clone();
+            kb.extend(panel_kb.sanitized(self.config.general.read_only))
+        } else {
+            kb
+        }
+    }
+
+    fn resolve_key(&self, key: Key) -> Vec<ExternalMsg> {
+        let kb = self.key_bindings();
         let key_str = key.to_string();
-        let msgs = kb
-            .on_key
+        kb.on_key
             .get(&key_str)
             .map(|a| a.messages.clone())
             .or_else(|| {
@@ -731,7 +1059,25 @@ impl App {
                 } else {
                     vec![ExternalMsg::LogWarning("key map not found.".into())]
                 }
+            })
+    }
+
+    fn enqueue_key_msgs(mut self, key: Key, mut msgs: Vec<ExternalMsg>) -> Self {
+        if msgs.iter().any(ExternalMsg::is_repeatable)
+            && !msgs.contains(&ExternalMsg::RepeatLastAction)
+        {
+            self.last_action = Some(LastAction {
+                key,
+                messages: msgs.clone(),
+                input_buffer: self.input.buffer.as_ref().map(|b| b.value().into()),
+                count: self.count,
             });
+        }
+
+        // The count prefix is only passed to the next action.
+        if self.count.is_some() && !msgs.contains(&ExternalMsg::UpdateCountFromKey) {
+            msgs.push(ExternalMsg::ClearCount);
+        }
 
         for msg in msgs {
             // Rename breaks without enqueue
@@ -740,11 +1086,14 @@ impl App {
             let msg_out = MsgOut::Enqueue(task);
             self.msg_out.push_back(msg_out);
         }
-
-        Ok(self)
+        self
     }
 
     pub fn explore_pwd(mut self) -> Result<Self> {
+        if self.duplicates.is_some() {
+            return self.refresh_duplicates();
+        }
+
         let focus = &self.last_focus.get(&self.pwd).cloned().unwrap_or(None);
         let pwd = self.pwd.clone();
         self = self.add_last_focus(pwd.clone(), focus.clone())?;
@@ -764,6 +1113,10 @@ impl App {
     }
 
     fn explore_pwd_async(mut self) -> Result<Self> {
+        if self.duplicates.is_some() {
+            return self.refresh_duplicates();
+        }
+
         self.msg_out.push_back(MsgOut::ExplorePwdAsync);
         Ok(self)
     }
@@ -1075,6 +1428,7 @@ impl App {
 
         match env::set_current_dir(&dir) {
             Ok(()) => {
+                self.duplicates = None;
                 let lwd = self.pwd.clone();
                 let focus = self.focused_node().map(|n| n.relative_path.clone());
                 self = self.add_last_focus(lwd, focus)?;
@@ -1209,6 +1563,173 @@ impl App {
         }
     }
 
+    fn update_count_from_key(mut self, key: Option<Key>) -> Result<Self> {
+        if let Some(digit) = key.and_then(|k| k.to_char()).and_then(|c| c.to_digit(10)) {
+            let count = self
+                .count
+                .unwrap_or_default()
+                .saturating_mul(10)
+                .saturating_add(digit as usize);
+            self.count = Some(count).filter(|c| *c > 0);
+        }
+        Ok(self)
+    }
+
+    fn set_count(mut self, count: usize) -> Result<Self> {
+        self.count = Some(count).filter(|c| *c > 0);
+        Ok(self)
+    }
+
+    fn clear_count(mut self) -> Result<Self> {
+        self.count = None;
+        Ok(self)
+    }
+
+    fn repeat(
+        mut self,
+        times: usize,
+        msgs: Vec<ExternalMsg>,
+        key: Option<Key>,
+    ) -> Result<Self> {
+        let times = times.saturating_mul(self.count.unwrap_or(1));
+        for _ in 0..times {
+            for msg in msgs.iter() {
+                let task = Task::new(MsgIn::External(msg.clone()), key);
+                self.msg_out.push_back(MsgOut::Enqueue(task));
+            }
+        }
+        Ok(self)
+    }
+
+    fn repeat_last_action(mut self) -> Result<Self> {
+        let Some(action) = self.last_action.clone() else {
+            return Ok(self);
+        };
+
+        let mut msgs = vec![];
+        if let Some(count) = self.count.or(action.count) {
+            msgs.push(ExternalMsg::SetCount(count));
+        }
+        if let Some(input) = action.input_buffer.clone() {
+            msgs.push(ExternalMsg::SetInputBuffer(input));
+        }
+        msgs.extend(action.messages);
+        msgs.push(ExternalMsg::ClearCount);
+        if self.input.buffer.is_none() && action.input_buffer.is_some() {
+            msgs.push(ExternalMsg::ResetInputBuffer);
+        }
+
+        for msg in msgs {
+            let task = Task::new(MsgIn::External(msg), Some(action.key));
+            self.msg_out.push_back(MsgOut::Enqueue(task));
+        }
+        Ok(self)
+    }
+
+    fn start_macro_recording(mut self, register: String) -> Result<Self> {
+        self.macro_recording = Some(MacroRecording {
+            register,
+            mode: self.mode.name.clone(),
+            keys: Default::default(),
+            checkpoint: Default::default(),
+        });
+        Ok(self)
+    }
+
+    fn start_macro_recording_from_key(self, key: Option<Key>) -> Result<Self> {
+        if let Some(key) = key {
+            self.start_macro_recording(key.to_string())
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn stop_macro_recording(mut self) -> Result<Self> {
+        let Some(mut rec) = self.macro_recording.take() else {
+            return Ok(self);
+        };
+
+        rec.keys.truncate(rec.checkpoint);
+        self.set_macro(Macro {
+            register: rec.register,
+            keys: rec.keys,
+        })
+    }
+
+    fn play_macro(mut self, register: &str) -> Result<Self> {
+        let Some(keys) = self.macros.get(register) else {
+            return self.log_error(format!("macro not found: {register}"));
+        };
+
+        if self.macro_playback.len() + keys.len() > MAX_MACRO_PLAYBACK_KEYS {
+            self.macro_playback.clear();
+            return self.log_error(format!("macro is too long: {register}"));
+        }
+
+        // Play the nested macros before continuing with the current one.
+        let was_playing = !self.macro_playback.is_empty();
+        for key in keys.iter().rev() {
+            self.macro_playback.push_front(Key::from(key.as_str()));
+        }
+
+        if !was_playing {
+            let task = Task::new(MsgIn::Internal(InternalMsg::PlayNextMacroKey), None);
+            self.msg_out.push_back(MsgOut::Enqueue(task));
+        }
+        Ok(self)
+    }
+
+    fn play_macro_from_key(self, key: Option<Key>) -> Result<Self> {
+        if let Some(key) = key {
+            self.play_macro(&key.to_string())
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Handle the next key of the macro being played. The rest are handled
+    /// after the messages enqueued by it.
+    fn play_next_macro_key(mut self) -> Result<Self> {
+        let Some(key) = self.macro_playback.pop_front() else {
+            return Ok(self);
+        };
+
+        // The keys used to play the macro are already being recorded.
+        let recording = self.macro_recording.take();
+        self = self.handle_key(key)?;
+        if self.macro_recording.is_none() {
+            self.macro_recording = recording;
+        }
+
+        let task = Task::new(MsgIn::Internal(InternalMsg::PlayNextMacroKey), None);
+        self.msg_out.push_back(MsgOut::Enqueue(task));
+        Ok(self)
+    }
+
+    fn set_macro(mut self, m: Macro) -> Result<Self> {
+        if m.keys.is_empty() {
+            self.macros.remove(&m.register);
+        } else {
+            self.macros.insert(m.register, m.keys);
+        }
+
+        if let Err(e) = self.save_macros() {
+            self = self.log_error(format!("could not save macros: {e}"))?;
+        }
+        Ok(self)
+    }
+
+    /// The file in the session dir to save the macros in.
+    pub fn macros_file(&self) -> PathBuf {
+        PathBuf::from(&self.session_path).join("macros.json")
+    }
+
+    fn save_macros(&self) -> Result<()> {
+        fs::create_dir_all(&self.session_path)?;
+        fs::write(self.macros_file(), serde_json::to_string(&self.macros)?)?;
+        Ok(())
+    }
+
     fn set_input_buffer(mut self, string: String) -> Result<Self> {
         self.input.buffer = Some(Input::default().with_value(string));
         self.logs_hidden = true;
@@ -1403,6 +1924,9 @@ impl App {
                 self = self.handle_batch_external_msgs(msgs)?
             }
 
+            let event = socket::Event::ModeSwitch(self.mode.name.clone());
+            self = self.publish_event(event);
+
             Ok(self)
         } else {
             self.log_error(format!("builtin mode not found: {mode:?}"))
@@ -1428,6 +1952,9 @@ impl App {
                 self = self.handle_batch_external_msgs(msgs)?
             }
 
+            let event = socket::Event::ModeSwitch(self.mode.name.clone());
+            self = self.publish_event(event);
+
             Ok(self)
         } else {
             self.log_error(format!("custom mode not found: {mode:?}"))
@@ -1540,6 +2067,11 @@ impl App {
         Ok(self)
     }
 
+    fn call_lua_async(mut self, func: String) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::CallLuaAsync(func));
+        Ok(self)
+    }
+
     fn lua_eval(mut self, code: String) -> Result<Self> {
         self.logs_hidden = true;
         self.msg_out.push_back(MsgOut::LuaEval(code));
@@ -1553,11 +2085,13 @@ impl App {
     }
 
     pub fn set_directory(mut self, dir: DirectoryBuffer) -> Result<Self> {
-        if self
-            .directory_buffer
-            .as_ref()
-            .map(|d| d.explored_at >= dir.explored_at)
-            .unwrap_or(false)
+        // The duplicate groups are being listed.
+        if self.duplicates.is_some()
+            || self
+                .directory_buffer
+                .as_ref()
+                .map(|d| d.explored_at >= dir.explored_at)
+                .unwrap_or(false)
         {
             return Ok(self);
         };
@@ -1605,7 +2139,12 @@ impl App {
         let count = self.selection.len();
 
         if let (Some(p), Some(n)) = (parent, filename) {
-            self.selection.insert(Node::new(p, n));
+            let node = Node::new(p, n);
+            if self.explorer_config.load_extended_metadata {
+                self.selection.insert(node.with_extended_metadata());
+            } else {
+                self.selection.insert(node);
+            }
         }
 
         if self.selection.len() != count {
@@ -1946,61 +2485,774 @@ impl App {
         Ok(self)
     }
 
-    fn enable_mouse(mut self) -> Result<Self> {
-        self.msg_out.push_back(MsgOut::EnableMouse);
-        Ok(self)
-    }
+    fn find_duplicates(mut self, path: &str) -> Result<Self> {
+        let path = PathBuf::from(path).absolutize()?.to_path_buf();
+        if !path.is_dir() {
+            return self.log_error(format!(
+                "not a valid directory: {:?}",
+                path.to_string_lossy()
+            ));
+        }
 
-    fn disable_mouse(mut self) -> Result<Self> {
-        self.msg_out.push_back(MsgOut::DisableMouse);
-        Ok(self)
-    }
+        let path = path.to_string_lossy().to_string();
+        self = self.change_directory(&path, true)?;
+        if self.pwd != path {
+            return Ok(self);
+        }
 
-    fn toggle_mouse(mut self) -> Result<Self> {
-        self.msg_out.push_back(MsgOut::ToggleMouse);
-        Ok(self)
+        self.msg_out
+            .push_back(MsgOut::FindDuplicatesAsync(path.clone()));
+        self.log_info(format!("finding duplicates in {path:?}"))
     }
 
-    fn start_fifo(mut self, path: String) -> Result<Self> {
-        self.msg_out.push_back(MsgOut::StartFifo(path));
-        Ok(self)
-    }
+    fn set_duplicates(mut self, dups: Duplicates) -> Result<Self> {
+        if dups.root != self.pwd {
+            return Ok(self);
+        }
 
-    fn stop_fifo(mut self) -> Result<Self> {
-        self.msg_out.push_back(MsgOut::StopFifo);
-        Ok(self)
-    }
+        let count = dups.groups.len();
+        let root = dups.root.clone();
+        if count == 0 {
+            return self.log_success(format!("no duplicates found in {root:?}"));
+        }
 
-    fn toggle_fifo(mut self, path: String) -> Result<Self> {
-        self.msg_out.push_back(MsgOut::ToggleFifo(path));
-        Ok(self)
+        self.directory_buffer = Some(dups.to_directory_buffer(0));
+        self.duplicates = Some(dups);
+        self.log_success(format!("found {count} duplicate groups in {root:?}"))
     }
 
-    pub fn log_info(mut self, message: String) -> Result<Self> {
-        self.logs_hidden = false;
-        self.logs.push(Log::new(LogLevel::Info, message));
-        Ok(self)
-    }
+    fn set_duplicates_buffer(mut self, dups: Duplicates) -> Result<Self> {
+        let focus = self.focused_node().map(|n| n.absolute_path.clone());
+        let fallback = self.directory_buffer.as_ref().map(|d| d.focus).unwrap_or(0);
+        let mut buf = dups.to_directory_buffer(fallback);
+        if let Some(i) =
+            focus.and_then(|f| buf.nodes.iter().position(|n| n.absolute_path == f))
+        {
+            buf.focus = i;
+        }
 
-    pub fn log_success(mut self, message: String) -> Result<Self> {
-        self.logs_hidden = false;
-        self.logs.push(Log::new(LogLevel::Success, message));
+        self.directory_buffer = Some(buf);
+        self.duplicates = Some(dups);
         Ok(self)
     }
 
-    pub fn log_warning(mut self, message: String) -> Result<Self> {
-        self.logs_hidden = false;
-        self.logs.push(Log::new(LogLevel::Warning, message));
-        Ok(self)
+    fn refresh_duplicates(self) -> Result<Self> {
+        if let Some(dups) = self.duplicates.clone() {
+            self.set_duplicates_buffer(dups.refreshed())
+        } else {
+            Ok(self)
+        }
     }
 
-    pub fn log_error(mut self, message: String) -> Result<Self> {
-        self.logs_hidden = false;
-        self.logs.push(Log::new(LogLevel::Error, message));
-        Ok(self)
+    fn toggle_duplicate_group(self) -> Result<Self> {
+        let focus = self.focused_node().map(|n| n.absolute_path.clone());
+        if let (Some(dups), Some(path)) = (self.duplicates.clone(), focus) {
+            self.set_duplicates_buffer(dups.toggle_group(&path))
+        } else {
+            Ok(self)
+        }
     }
 
-    fn quit(mut self) -> Result<Self> {
+    fn select_duplicates(mut self) -> Result<Self> {
+        let count = self.selection.len();
+        if let Some(dups) = self.duplicates.as_ref() {
+            self.selection.extend(dups.redundant_nodes());
+        }
+
+        if self.selection.len() != count {
+            self = self.on_selection_change()?;
+        }
+
+        Ok(self)
+    }
+
+    fn clear_duplicates(mut self) -> Result<Self> {
+        if self.duplicates.take().is_some() {
+            self.explore_pwd()
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn hash(mut self, opts: HashOptions) -> Result<Self> {
+        let paths: Vec<String> = self
+            .result()
+            .into_iter()
+            .filter(|n| {
+                n.is_file || n.symlink.as_ref().map(|s| s.is_file).unwrap_or(false)
+            })
+            .filter(|n| {
+                !self
+                    .hashes
+                    .get(&n.absolute_path)
+                    .map(|h| h.is_fresh(n) && h.digests.contains_key(&opts.algo))
+                    .unwrap_or(false)
+            })
+            .map(|n| n.absolute_path.clone())
+            .collect();
+
+        if !paths.is_empty() {
+            self.msg_out.push_back(MsgOut::HashAsync(paths, opts.algo));
+        }
+        Ok(self)
+    }
+
+    fn copy_hash(mut self, opts: HashOptions) -> Result<Self> {
+        let Some(node) = self.focused_node().cloned() else {
+            return Ok(self);
+        };
+
+        let cached = self
+            .hashes
+            .get(&node.absolute_path)
+            .filter(|h| h.is_fresh(&node))
+            .and_then(|h| h.digests.get(&opts.algo))
+            .cloned();
+
+        if let Some(digest) = cached {
+            self.msg_out.push_back(MsgOut::CopyToClipboard(digest));
+            return self.log_success(format!(
+                "copied {0} hash of {1:?}",
+                opts.algo, node.absolute_path
+            ));
+        }
+
+        // Copy it once the hash arrives, without blocking the UI.
+        self.hash_to_copy = Some((node.absolute_path.clone(), opts.algo));
+        self.msg_out
+            .push_back(MsgOut::HashAsync(vec![node.absolute_path], opts.algo));
+        Ok(self)
+    }
+
+    fn verify_checksum_file(mut self, path: &str) -> Result<Self> {
+        let path = PathBuf::from(path)
+            .absolutize()?
+            .to_string_lossy()
+            .to_string();
+        self.msg_out
+            .push_back(MsgOut::VerifyChecksumFileAsync(path.clone()));
+        self.log_info(format!("verifying checksums in {path:?}"))
+    }
+
+    fn set_node_hashes(mut self, hashes: Vec<NodeHash>) -> Result<Self> {
+        let (mut ok, mut mismatch, mut missing, mut failed) = (0, 0, 0, 0);
+        for hash in hashes {
+            // Whether it's hashed or not, the pending copy is done with.
+            let copy = self.hash_to_copy.as_ref().is_some_and(|(path, algo)| {
+                path == &hash.absolute_path && algo == &hash.algo
+            });
+            if copy {
+                self.hash_to_copy = None;
+            }
+
+            if let Some(err) = hash.error.as_ref() {
+                failed += 1;
+                self = self.log_error(format!(
+                    "could not hash {0:?}: {err}",
+                    hash.absolute_path
+                ))?;
+                continue;
+            }
+
+            match hash.checksum {
+                Some(ChecksumStatus::Ok) => ok += 1,
+                Some(ChecksumStatus::Mismatch) => {
+                    mismatch += 1;
+                    self = self.log_error(format!(
+                        "checksum mismatch: {:?}",
+                        hash.absolute_path
+                    ))?;
+                }
+                Some(ChecksumStatus::Missing) => {
+                    missing += 1;
+                    self = self.log_warning(format!(
+                        "missing file: {:?}",
+                        hash.absolute_path
+                    ))?;
+                }
+                None => {}
+            }
+
+            if let Some(digest) = hash.digest.as_ref().filter(|_| copy) {
+                self.msg_out
+                    .push_back(MsgOut::CopyToClipboard(digest.clone()));
+                self = self.log_success(format!(
+                    "copied {0} hash of {1:?}",
+                    hash.algo, hash.absolute_path
+                ))?;
+            }
+
+            self.hashes
+                .entry(hash.absolute_path.clone())
+                .or_default()
+                .update(hash);
+        }
+
+        // The failures are only summed up when verifying the checksums.
+        if ok + mismatch + missing == 0 {
+            Ok(self)
+        } else if mismatch + missing + failed > 0 {
+            self.log_warning(format!(
+                "checksums: {ok} ok, {mismatch} mismatch, {missing} missing, {failed} failed"
+            ))
+        } else {
+            self.log_success(format!("checksums: {ok} ok"))
+        }
+    }
+
+    fn handle_socket_request(mut self, id: usize, req: socket::Request) -> Result<Self> {
+        use socket::Request::*;
+        let response: socket::Response = match req {
+            GetPwd => Ok(self.pwd.clone().into()),
+            GetSelection => {
+                serde_json::to_value(&self.selection).map_err(|e| e.to_string())
+            }
+            GetFocusedNode => {
+                serde_json::to_value(self.focused_node()).map_err(|e| e.to_string())
+            }
+            GetAppState => serde_json::to_value(&self).map_err(|e| e.to_string()),
+            // Handled by the server itself.
+            Subscribe => Ok(serde_json::Value::Null),
+            Msg(msg) => {
+                // Report the errors logged while handling the message, using
+                // the log IDs, as the old logs may be dropped meanwhile.
+                let first = self.logs_dropped + self.logs.len();
+                self = self.handle_external(msg, None)?;
+                let errors = self
+                    .logs
+                    .iter()
+                    .skip(first.saturating_sub(self.logs_dropped))
+                    .filter(|l| l.level == LogLevel::Error)
+                    .map(|l| l.message.clone())
+                    .collect::<Vec<_>>();
+                if errors.is_empty() {
+                    Ok(serde_json::Value::Null)
+                } else {
+                    Err(errors.join("\n"))
+                }
+            }
+        };
+
+        // Reply before quitting, as nothing is handled after that.
+        let response = MsgOut::SocketReply(id, serde_json::to_string(&response)?);
+        let quit = self.msg_out.iter().position(|m| {
+            matches!(
+                m,
+                MsgOut::Quit
+                    | MsgOut::PrintPwdAndQuit
+                    | MsgOut::PrintFocusPathAndQuit
+                    | MsgOut::PrintSelectionAndQuit
+                    | MsgOut::PrintResultAndQuit
+                    | MsgOut::PrintAppStateAndQuit
+            )
+        });
+        match quit {
+            Some(i) => self.msg_out.insert(i, response),
+            None => self.msg_out.push_back(response),
+        }
+        Ok(self)
+    }
+
+    fn set_xattr(mut self, attr: Xattr) -> Result<Self> {
+        let paths: Vec<String> = self
+            .result()
+            .into_iter()
+            .map(|n| n.absolute_path.clone())
+            .collect();
+
+        let mut count = 0;
+        for path in paths {
+            match xattr::set(Path::new(&path), &attr.name, attr.value.as_bytes()) {
+                Ok(()) => count += 1,
+                Err(e) => {
+                    self = self.log_error(format!(
+                        "could not set {0:?} on {path:?}: {e}",
+                        attr.name
+                    ))?
+                }
+            }
+        }
+
+        if count > 0 {
+            self =
+                self.log_success(format!("set {0:?} on {count} path(s)", attr.name))?;
+        }
+        self.explore_pwd_async()
+    }
+
+    fn set_xattr_from_input(self, name: String) -> Result<Self> {
+        let value = self
+            .input
+            .buffer
+            .as_ref()
+            .map(|b| b.value().to_string())
+            .unwrap_or_default();
+        self.set_xattr(Xattr { name, value })
+    }
+
+    fn remove_xattr(mut self, name: &str) -> Result<Self> {
+        let paths: Vec<String> = self
+            .result()
+            .into_iter()
+            .map(|n| n.absolute_path.clone())
+            .collect();
+
+        let mut count = 0;
+        for path in paths {
+            match xattr::remove(Path::new(&path), name) {
+                Ok(()) => count += 1,
+                Err(e) => {
+                    self = self.log_error(format!(
+                        "could not remove {name:?} from {path:?}: {e}"
+                    ))?
+                }
+            }
+        }
+
+        if count > 0 {
+            self = self.log_success(format!("removed {name:?} from {count} path(s)"))?;
+        }
+        self.explore_pwd_async()
+    }
+
+    fn set_permissions(mut self, octal: &str, recursive: bool) -> Result<Self> {
+        let Some(perms) = Permissions::from_octal(octal) else {
+            return self.log_error(format!("invalid permissions: {octal:?}"));
+        };
+
+        let paths: Vec<String> = self
+            .result()
+            .into_iter()
+            .map(|n| n.absolute_path.clone())
+            .collect();
+
+        let mut changed = 0;
+        for path in paths {
+            let report =
+                permissions::set_permissions(Path::new(&path), perms, recursive);
+            changed += report.changed;
+            for (path, err) in report.errors {
+                self = self.log_error(format!("could not chmod {path:?}: {err}"))?;
+            }
+        }
+
+        if changed > 0 {
+            self = self.log_success(format!(
+                "changed permissions of {changed} path(s) to {perms}"
+            ))?;
+        }
+        self.explore_pwd_async()
+    }
+
+    fn set_permissions_from_input(self, recursive: bool) -> Result<Self> {
+        let octal = self
+            .input
+            .buffer
+            .as_ref()
+            .map(|b| b.value().to_string())
+            .unwrap_or_default();
+        self.set_permissions(&octal, recursive)
+    }
+
+    fn change_owner(mut self, owner: Ownership, recursive: bool) -> Result<Self> {
+        let (uid, gid) = match owner.resolve() {
+            Ok(ids) => ids,
+            Err(e) => return self.log_error(format!("could not chown: {e}")),
+        };
+
+        let paths: Vec<String> = self
+            .result()
+            .into_iter()
+            .map(|n| n.absolute_path.clone())
+            .collect();
+
+        let mut changed = 0;
+        for path in paths {
+            let report =
+                permissions::change_owner(Path::new(&path), uid, gid, recursive);
+            changed += report.changed;
+            for (path, err) in report.errors {
+                self = self.log_error(format!("could not chown {path:?}: {err}"))?;
+            }
+        }
+
+        if changed > 0 {
+            self = self.log_success(format!("changed owner of {changed} path(s)"))?;
+        }
+        self.explore_pwd_async()
+    }
+
+    /// The index of the node under the mouse pointer, if any.
+    fn mouse_index(&self) -> Option<usize> {
+        let total = self.directory_buffer.as_ref().map(|d| d.total)?;
+        self.mouse.and_then(|m| m.index).filter(|i| *i < total)
+    }
+
+    fn focus_from_mouse(self) -> Result<Self> {
+        if let Some(index) = self.mouse_index() {
+            self.focus_by_index(index)
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn toggle_selection_from_mouse(self) -> Result<Self> {
+        let path = self.mouse_index().and_then(|i| {
+            self.directory_buffer
+                .as_ref()
+                .and_then(|d| d.nodes.get(i))
+                .map(|n| n.absolute_path.clone())
+        });
+
+        if let Some(path) = path {
+            self.toggle_selection_by_path(path)
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn select_range_from_mouse(mut self) -> Result<Self> {
+        let (Some(index), Some(dir)) =
+            (self.mouse_index(), self.directory_buffer.as_ref())
+        else {
+            return Ok(self);
+        };
+
+        let count = self.selection.len();
+        let (start, end) = (dir.focus.min(index), dir.focus.max(index));
+        let nodes = dir.nodes.iter().skip(start).take(end - start + 1).cloned();
+        self.selection.extend(nodes.collect::<Vec<Node>>());
+
+        if self.selection.len() != count {
+            self = self.on_selection_change()?;
+        }
+
+        Ok(self)
+    }
+
+    fn enable_mouse(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::EnableMouse);
+        Ok(self)
+    }
+
+    fn disable_mouse(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::DisableMouse);
+        Ok(self)
+    }
+
+    fn toggle_mouse(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::ToggleMouse);
+        Ok(self)
+    }
+
+    /// The name of the focused panel, other than the table.
+    pub fn focused_panel_name(&self) -> Option<&str> {
+        self.focused_panel.as_ref().map(|p| p.name.as_str())
+    }
+
+    /// The mode to show the help menu of. While the help menu is focused, it
+    /// keeps showing the mode it was focused in, e.g. to be searched.
+    pub fn help_menu_mode(&self) -> Mode {
+        let modes = &self.config.modes;
+        self.focused_panel
+            .as_ref()
+            .filter(|p| p.name == "HelpMenu" && p.mode != self.mode.name)
+            .and_then(|p| {
+                modes
+                    .builtin
+                    .values()
+                    .chain(modes.custom.values())
+                    .find(|m| m.name == p.mode)
+            })
+            .map(|m| {
+                m.clone().sanitized(
+                    self.config.general.read_only,
+                    self.config.general.global_key_bindings.clone(),
+                )
+            })
+            .unwrap_or_else(|| self.mode.clone())
+    }
+
+    fn focus_panel(mut self, name: String) -> Result<Self> {
+        if name == TABLE_PANEL {
+            self.focused_panel = None;
+        } else {
+            self.focused_panel = Some(PanelFocus {
+                name,
+                mode: self.mode.name.clone(),
+            });
+        }
+        Ok(self)
+    }
+
+    fn unfocus_panel(mut self) -> Result<Self> {
+        self.focused_panel = None;
+        Ok(self)
+    }
+
+    fn cycle_panel_focus(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::CyclePanelFocus);
+        Ok(self)
+    }
+
+    fn move_panel_cursor(mut self, offset: isize) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::MovePanelCursor(offset));
+        Ok(self)
+    }
+
+    fn activate_panel_row(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::ActivatePanelRow);
+        Ok(self)
+    }
+
+    fn search_panel(mut self, query: String) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::SearchPanel(query));
+        Ok(self)
+    }
+
+    fn search_panel_from_input(self) -> Result<Self> {
+        if let Some(query) = self.input.buffer.as_ref().map(|b| b.value().to_string()) {
+            self.search_panel(query)
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn search_panel_next(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::SearchPanelNext);
+        Ok(self)
+    }
+
+    fn search_panel_previous(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::SearchPanelPrevious);
+        Ok(self)
+    }
+
+    fn enqueue_after(mut self, delayed: DelayedMessages) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::EnqueueAfter(delayed));
+        Ok(self)
+    }
+
+    fn cancel_timer(mut self, id: String) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::CancelTimer(id));
+        Ok(self)
+    }
+
+    fn call_lua_timer(mut self, id: String) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::CallLuaTimer(id));
+        Ok(self)
+    }
+
+    fn start_fifo(mut self, path: String) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::StartFifo(path));
+        Ok(self)
+    }
+
+    fn stop_fifo(mut self) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::StopFifo);
+        Ok(self)
+    }
+
+    fn toggle_fifo(mut self, path: String) -> Result<Self> {
+        self.msg_out.push_back(MsgOut::ToggleFifo(path));
+        Ok(self)
+    }
+
+    fn open_command_palette(mut self) -> Result<Self> {
+        self.command_palette = Some(CommandPalette::new(
+            &self.config.modes,
+            &self.config.general.global_key_bindings,
+            self.config.general.read_only,
+        ));
+        self.switch_mode_builtin(COMMAND_PALETTE_MODE)
+    }
+
+    fn search_command_palette_from_input(mut self) -> Result<Self> {
+        let pattern = self
+            .input
+            .buffer
+            .as_ref()
+            .map(|b| b.value().to_string())
+            .unwrap_or_default();
+        self.command_palette = self.command_palette.map(|p| p.search(&pattern));
+        Ok(self)
+    }
+
+    fn focus_next_command_palette_item(mut self) -> Result<Self> {
+        self.command_palette = self.command_palette.map(CommandPalette::focus_next);
+        Ok(self)
+    }
+
+    fn focus_previous_command_palette_item(mut self) -> Result<Self> {
+        self.command_palette = self.command_palette.map(CommandPalette::focus_previous);
+        Ok(self)
+    }
+
+    fn execute_command_palette_item(mut self) -> Result<Self> {
+        let msgs = self
+            .command_palette
+            .as_ref()
+            .and_then(|p| p.focused_item())
+            .map(|i| i.messages.clone())
+            .unwrap_or_default();
+
+        self = self.close_command_palette()?;
+        for msg in msgs {
+            let task = Task::new(MsgIn::External(msg), None);
+            self.msg_out.push_back(MsgOut::Enqueue(task));
+        }
+        Ok(self)
+    }
+
+    fn close_command_palette(mut self) -> Result<Self> {
+        if self.command_palette.take().is_some() {
+            self.pop_mode()
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn execute_command_line(mut self, line: &str) -> Result<Self> {
+        let cmd = match CommandLine::parse(line) {
+            Ok(cmd) => cmd,
+            Err(e) => return self.log_error(e.to_string()),
+        };
+
+        let is_select = matches!(cmd, CommandLine::Select(_));
+        let nodes = self
+            .directory_buffer
+            .as_ref()
+            .map(|d| d.nodes.as_slice())
+            .unwrap_or_default();
+        let msgs = cmd.messages(nodes, self.focused_node());
+
+        if is_select && msgs.is_empty() {
+            return self.log_warning(format!("no match found for {line:?}"));
+        }
+
+        for msg in msgs {
+            let task = Task::new(MsgIn::External(msg), None);
+            self.msg_out.push_back(MsgOut::Enqueue(task));
+        }
+        Ok(self)
+    }
+
+    fn execute_command_line_from_input(mut self) -> Result<Self> {
+        let line = self
+            .input
+            .buffer
+            .as_ref()
+            .map(|b| b.value().to_string())
+            .unwrap_or_default();
+
+        self.command_line_history = self.command_line_history.push(line.clone());
+        self.execute_command_line(&line)
+    }
+
+    fn complete_command_line(self) -> Result<Self> {
+        let line = self.input.buffer.as_ref().map(|b| b.value().to_string());
+        match line.map(|l| command_line::complete(&l, &self.pwd)) {
+            Some(Ok(Some(completed))) => self.set_input_buffer(completed),
+            Some(Err(e)) => self.log_error(e.to_string()),
+            _ => Ok(self),
+        }
+    }
+
+    fn previous_command_line(mut self) -> Result<Self> {
+        self.command_line_history = self.command_line_history.previous();
+        let line = self.command_line_history.peek().cloned();
+        self.set_input_buffer(line.unwrap_or_default())
+    }
+
+    fn next_command_line(mut self) -> Result<Self> {
+        self.command_line_history = self.command_line_history.next();
+        let line = self.command_line_history.peek().cloned();
+        self.set_input_buffer(line.unwrap_or_default())
+    }
+
+    pub fn log_info(self, message: String) -> Result<Self> {
+        self.push_log(Log::new(LogLevel::Info, message))
+    }
+
+    pub fn log_success(self, message: String) -> Result<Self> {
+        self.push_log(Log::new(LogLevel::Success, message))
+    }
+
+    pub fn log_warning(self, message: String) -> Result<Self> {
+        self.push_log(Log::new(LogLevel::Warning, message))
+    }
+
+    pub fn log_error(self, message: String) -> Result<Self> {
+        self.push_log(Log::new(LogLevel::Error, message))
+    }
+
+    fn push_log(mut self, log: Log) -> Result<Self> {
+        self.logs_hidden = false;
+
+        if self.config.general.logs.write_to_file {
+            self.msg_out
+                .push_back(MsgOut::WriteLogFile(format!("{0} {log}", self.pid)));
+        }
+
+        self.logs.push(log);
+
+        if let Some(max) = self.config.general.logs.max_entries {
+            let excess = self.logs.len().saturating_sub(max);
+            self.logs.drain(..excess);
+            self.logs_dropped += excess;
+        }
+
+        Ok(self)
+    }
+
+    /// The logs passing the log level filter, along with their IDs. The ID
+    /// of a log is its index, counting the logs dropped from the history.
+    pub fn filtered_logs(&self) -> impl DoubleEndedIterator<Item = (usize, &Log)> {
+        self.logs
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (self.logs_dropped + i, l))
+            .filter(|(_, l)| self.log_level_filter.is_none_or(|f| f == l.level))
+    }
+
+    fn set_log_level_filter(mut self, level: LogLevel) -> Result<Self> {
+        self.log_level_filter = Some(level);
+        Ok(self)
+    }
+
+    fn unset_log_level_filter(mut self) -> Result<Self> {
+        self.log_level_filter = None;
+        Ok(self)
+    }
+
+    fn copy_log(mut self, id: usize) -> Result<Self> {
+        let log = id
+            .checked_sub(self.logs_dropped)
+            .and_then(|i| self.logs.get(i));
+
+        if let Some(log) = log {
+            self.msg_out
+                .push_back(MsgOut::CopyToClipboard(log.to_string()));
+            Ok(self)
+        } else {
+            self.log_warning(format!("log {id} is no longer available"))
+        }
+    }
+
+    fn copy_logs(mut self) -> Result<Self> {
+        let logs = self
+            .filtered_logs()
+            .map(|(_, l)| l.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        if !logs.is_empty() {
+            self.msg_out.push_back(MsgOut::CopyToClipboard(logs));
+        }
+        Ok(self)
+    }
+
+    fn quit(mut self) -> Result<Self> {
         self.msg_out.push_back(MsgOut::Quit);
         Ok(self)
     }
@@ -2065,7 +3317,15 @@ impl App {
             self = self.handle_batch_external_msgs(msgs)?
         }
 
-        Ok(self)
+        let selection = self.selection.iter().cloned().collect();
+        Ok(self.publish_event(socket::Event::SelectionChange(selection)))
+    }
+
+    fn publish_event(mut self, event: socket::Event) -> Self {
+        if self.config.general.start_socket {
+            self.msg_out.push_back(MsgOut::PublishEvent(event));
+        }
+        self
     }
 
     pub fn result(&self) -> Vec<&Node> {
@@ -2076,37 +3336,50 @@ impl App {
         }
     }
 
+    /// Format the nodes as per the configured output format.
+    pub fn nodes_str<'a>(
+        &self,
+        nodes: impl IntoIterator<Item = &'a Node>,
+        delimiter: char,
+    ) -> String {
+        let nodes = nodes.into_iter();
+        match self.config.general.output_format {
+            OutputFormat::Paths => nodes
+                .map(|n| format!("{0}{delimiter}", n.absolute_path))
+                .collect::<Vec<String>>()
+                .join(""),
+            OutputFormat::Jsonl => nodes
+                .filter_map(|n| serde_json::to_string(n).ok())
+                .map(|n| format!("{n}{delimiter}"))
+                .collect::<Vec<String>>()
+                .join(""),
+            OutputFormat::Json => {
+                let nodes = nodes.collect::<Vec<&Node>>();
+                let json = serde_json::to_string(&nodes).unwrap_or_default();
+                format!("{json}{delimiter}")
+            }
+        }
+    }
+
     pub fn directory_nodes_str(&self, delimiter: char) -> String {
-        self.directory_buffer
-            .as_ref()
-            .map(|d| {
-                d.nodes
-                    .iter()
-                    .map(|n| format!("{0}{delimiter}", n.absolute_path))
-                    .collect::<Vec<String>>()
-                    .join("")
-            })
-            .unwrap_or_default()
+        let nodes = self.directory_buffer.iter().flat_map(|d| d.nodes.iter());
+        self.nodes_str(nodes, delimiter)
     }
 
     pub fn pwd_str(&self, delimiter: char) -> String {
         format!("{0}{delimiter}", &self.pwd)
     }
 
+    pub fn focus_str(&self, delimiter: char) -> Option<String> {
+        self.focused_node().map(|n| self.nodes_str([n], delimiter))
+    }
+
     pub fn selection_str(&self, delimiter: char) -> String {
-        self.selection
-            .iter()
-            .map(|n| format!("{0}{delimiter}", n.absolute_path))
-            .collect::<Vec<String>>()
-            .join("")
+        self.nodes_str(&self.selection, delimiter)
     }
 
     pub fn result_str(&self, delimiter: char) -> String {
-        self.result()
-            .into_iter()
-            .map(|n| format!("{0}{delimiter}", n.absolute_path))
-            .collect::<Vec<String>>()
-            .join("")
+        self.nodes_str(self.result(), delimiter)
     }
 
     pub fn logs_str(&self, delimiter: char) -> String {
@@ -2220,6 +3493,8 @@ impl App {
             explorer_config: self.explorer_config.clone(),
             history: self.history.clone(),
             last_modes: self.last_modes.clone(),
+            count: self.count,
+            macros: self.macros.clone(),
         }
     }
 
@@ -2237,6 +3512,8 @@ impl App {
             pid: self.pid,
             session_path: self.session_path.clone(),
             explorer_config: self.explorer_config.clone(),
+            count: self.count,
+            macros: self.macros.clone(),
         }
     }
 }
@@ -2,6 +2,7 @@
 
 use std::env;
 use xplr::cli::{self, Cli};
+use xplr::plugin;
 use xplr::runner;
 
 fn main() {
@@ -18,6 +19,8 @@ fn main() {
   -                            Reads new-line (\n) separated paths from stdin
   --                           Denotes the end of command-line flags and options
       --force-focus            Focuses on the given <PATH>, even if it is a directory
+      --headless               Handles the messages read from stdin without a
+                                 terminal, and prints the result
   -h, --help                   Prints help information
   -m, --pipe-msg-in            Helps safely passing messages to the active xplr
                                  session, use %%, %s and %q as the placeholders
@@ -36,6 +39,11 @@ fn main() {
                                     "$HOME/.config/xplr/init.lua")
   -C, --extra-config <PATH>...    Specifies extra config files to load
       --on-load <MESSAGE>...      Sends messages when xplr loads
+      --output-format <FORMAT>    Prints paths, or nodes as json or jsonl
+                                    (config.general.output_format)
+      --plugins <COMMAND>         Installs, updates or lists the plugins in
+                                    config.plugins, COMMAND being one of
+                                    install, update and list
       --vroot <PATH>              Treats the specified path as the virtual root"###;
 
         let args = r###"
@@ -57,6 +65,11 @@ fn main() {
         println!("{help}");
     } else if cli.version {
         println!("xplr {}", xplr::app::VERSION);
+    } else if let Some(command) = cli.plugins {
+        if let Err(err) = plugin::manage(command, cli.config, cli.extra_config) {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
     } else if !cli.pipe_msg_in.is_empty() {
         if let Err(err) = cli::pipe_msg_in(cli.pipe_msg_in) {
             eprintln!("error: {err}");
@@ -68,7 +81,16 @@ fn main() {
             std::process::exit(1);
         }
     } else {
-        match runner::from_cli(cli).and_then(|a| a.run()) {
+        let headless = cli.headless;
+        let res = runner::from_cli(cli).and_then(|a| {
+            if headless {
+                a.run_headless()
+            } else {
+                a.run()
+            }
+        });
+
+        match res {
             Ok(Some(out)) => {
                 print!("{out}");
             }
@@ -1,3 +1,5 @@
+use crate::config::OutputFormat;
+use crate::plugin::PluginCommand;
 use crate::{app, yaml};
 use anyhow::{bail, Context, Result};
 use app::ExternalMsg;
@@ -15,8 +17,11 @@ pub struct Cli {
     pub version: bool,
     pub help: bool,
     pub read_only: bool,
+    pub headless: bool,
     pub force_focus: bool,
     pub print_pwd_as_result: bool,
+    pub output_format: Option<OutputFormat>,
+    pub plugins: Option<PluginCommand>,
     pub read0: bool,
     pub write0: bool,
     pub vroot: Option<PathBuf>,
@@ -130,6 +135,22 @@ impl Cli {
 
                     "--read-only" => cli.read_only = true,
 
+                    "--headless" => cli.headless = true,
+
+                    "--output-format" => {
+                        cli.output_format =
+                            Some(args.next().map(|a| a.parse()).with_context(
+                                || format!("usage: xplr {arg} json|jsonl|paths"),
+                            )??);
+                    }
+
+                    "--plugins" => {
+                        cli.plugins =
+                            Some(args.next().map(|a| a.parse()).with_context(
+                                || format!("usage: xplr {arg} install|update|list"),
+                            )??);
+                    }
+
                     "--on-load" => {
                         while let Some(msg) = args.next_if(|msg| !msg.starts_with('-')) {
                             cli.on_load.push(yaml::from_str(&msg)?);
@@ -0,0 +1,445 @@
+use crate::app::{ExternalMsg, Node};
+use crate::explorer;
+use crate::msg::in_::external::{
+    NodeFilter, NodeFilterApplicable, NodeSorter, NodeSorterApplicable,
+};
+use crate::path;
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The names of the commands, used for completion.
+pub const COMMANDS: [&str; 6] = ["cd", "filter", "q", "quit", "select", "sort"];
+
+/// The short names of the sorters accepted by `:sort`.
+const SORTERS: [(&str, &[NodeSorter]); 9] = [
+    ("created", &[NodeSorter::ByCreated]),
+    ("ext", &[NodeSorter::ByCanonicalExtension]),
+    ("group", &[NodeSorter::ByGroupName]),
+    ("mime", &[NodeSorter::ByCanonicalMimeEssence]),
+    ("modified", &[NodeSorter::ByLastModified]),
+    ("name", &[NodeSorter::ByIRelativePath]),
+    ("size", &[NodeSorter::BySize]),
+    (
+        "type",
+        &[
+            NodeSorter::ByCanonicalIsDir,
+            NodeSorter::ByCanonicalIsFile,
+            NodeSorter::ByIsSymlink,
+        ],
+    ),
+    ("user", &[NodeSorter::ByUserName]),
+];
+
+/// A parsed command line.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CommandLine {
+    /// `:cd [path]`
+    ChangeDirectory(String),
+
+    /// `:sort [sorter [rev]]`, clearing the sorters when empty.
+    Sort(Vec<NodeSorterApplicable>),
+
+    /// `:filter [[!]field op value]`, clearing the filters when empty.
+    Filter(Option<NodeFilterApplicable>),
+
+    /// `:select glob...`
+    Select(Vec<String>),
+
+    /// `:!command`, where `%s` is replaced with the focused path.
+    Shell(String),
+
+    /// `:q` or `:quit`
+    Quit,
+}
+
+impl CommandLine {
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let line = line.strip_prefix(':').unwrap_or(line).trim_start();
+
+        if let Some(cmd) = line.strip_prefix('!') {
+            if cmd.trim().is_empty() {
+                bail!("missing shell command")
+            }
+            return Ok(Self::Shell(cmd.into()));
+        }
+
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args = rest.split_whitespace().collect::<Vec<&str>>();
+
+        match (name, args.as_slice()) {
+            ("cd", []) => Ok(Self::ChangeDirectory(expand_home("~"))),
+            ("cd", _) => Ok(Self::ChangeDirectory(expand_home(rest))),
+            ("sort", [] | ["clear"]) => Ok(Self::Sort(vec![])),
+            ("sort", [sorter]) => parse_sorter(sorter, false).map(Self::Sort),
+            ("sort", [sorter, "rev" | "reverse"]) => {
+                parse_sorter(sorter, true).map(Self::Sort)
+            }
+            ("filter", [] | ["clear"]) => Ok(Self::Filter(None)),
+            ("filter", _) => parse_filter(rest).map(Some).map(Self::Filter),
+            ("select", []) => bail!("missing glob pattern"),
+            ("select", _) => {
+                Ok(Self::Select(args.into_iter().map(Into::into).collect()))
+            }
+            ("q" | "quit", []) => Ok(Self::Quit),
+            ("", _) => bail!("missing command"),
+            (name, _) if COMMANDS.contains(&name) => {
+                bail!("invalid arguments for {name:?}: {line:?}")
+            }
+            (name, _) => bail!("unknown command: {name:?}"),
+        }
+    }
+
+    /// The messages to run the command, given the nodes in the current
+    /// directory and the focused node.
+    pub fn messages(self, nodes: &[Node], focus: Option<&Node>) -> Vec<ExternalMsg> {
+        match self {
+            Self::ChangeDirectory(dir) => vec![ExternalMsg::ChangeDirectory(dir)],
+
+            Self::Sort(sorters) if sorters.is_empty() => {
+                vec![ExternalMsg::ClearNodeSorters, ExternalMsg::ExplorePwdAsync]
+            }
+
+            Self::Sort(sorters) => sorters
+                .into_iter()
+                .map(ExternalMsg::AddNodeSorter)
+                .chain([ExternalMsg::ExplorePwdAsync])
+                .collect(),
+
+            Self::Filter(None) => {
+                vec![ExternalMsg::ClearNodeFilters, ExternalMsg::ExplorePwdAsync]
+            }
+
+            Self::Filter(Some(filter)) => vec![
+                ExternalMsg::AddNodeFilter(filter),
+                ExternalMsg::ExplorePwdAsync,
+            ],
+
+            Self::Select(globs) => {
+                let regexes = globs
+                    .iter()
+                    .filter_map(|g| glob_to_regex(g).ok())
+                    .collect::<Vec<Regex>>();
+                nodes
+                    .iter()
+                    .filter(|n| regexes.iter().any(|r| r.is_match(&n.relative_path)))
+                    .map(|n| ExternalMsg::SelectPath(n.absolute_path.clone()))
+                    .collect()
+            }
+
+            Self::Shell(cmd) => {
+                let focus = focus
+                    .map(|n| path::escape(&n.absolute_path).to_string())
+                    .unwrap_or_default();
+                vec![ExternalMsg::BashExec(cmd.replace("%s", &focus))]
+            }
+
+            Self::Quit => vec![ExternalMsg::Quit],
+        }
+    }
+}
+
+pub(crate) fn expand_home(path: &str) -> String {
+    let home = path::HOME.as_ref().map(|h| h.to_string_lossy().to_string());
+    match (path, home) {
+        ("~", Some(home)) => home,
+        (p, Some(home)) if p.starts_with("~/") => format!("{home}{0}", &p[1..]),
+        (p, _) => p.into(),
+    }
+}
+
+fn parse_sorter(name: &str, reverse: bool) -> Result<Vec<NodeSorterApplicable>> {
+    let sorters = if let Some((_, sorters)) = SORTERS.iter().find(|(n, _)| *n == name) {
+        sorters.to_vec()
+    } else if let Ok(sorter) = serde_json::from_value::<NodeSorter>(name.into()) {
+        vec![sorter]
+    } else {
+        bail!("unknown sorter: {name:?}")
+    };
+
+    Ok(sorters
+        .into_iter()
+        .map(|sorter| NodeSorterApplicable { sorter, reverse })
+        .collect())
+}
+
+/// Parse filters like `ext=rs`, `name^=foo`, `!ipath*=bar`.
+///
+/// The fields are `name`, `path` and `ext`, prefixed with `i` for case
+/// insensitive matching, and the operators are `=` (is), `!=` (is not), `^=`
+/// (starts with), `$=` (ends with), `*=` (contains) and `~=` (matches regex).
+/// A leading `!` negates the filter.
+fn parse_filter(arg: &str) -> Result<NodeFilterApplicable> {
+    let Some((lhs, value)) = arg.split_once('=') else {
+        bail!("invalid filter: {arg:?}")
+    };
+
+    let (negate, lhs) = match lhs.strip_prefix('!') {
+        Some(lhs) => (true, lhs),
+        None => (false, lhs),
+    };
+
+    let (field, op) = match lhs.char_indices().last() {
+        Some((i, c @ ('!' | '^' | '$' | '*' | '~'))) => (&lhs[..i], Some(c)),
+        _ => (lhs, None),
+    };
+
+    let (case, field) = match field {
+        "name" | "path" | "ext" => ("", field),
+        "iname" | "ipath" | "iext" => ("I", &field[1..]),
+        _ => bail!("unknown filter field: {field:?}"),
+    };
+
+    let (subject, op, negated) = match (field, op) {
+        ("ext", None) => ("RelativePath", "EndWith", false),
+        ("ext", Some('!')) => ("RelativePath", "EndWith", true),
+        ("ext", _) => bail!("invalid operator for ext: {arg:?}"),
+        ("name", op) => ("RelativePath", filter_op(op), op == Some('!')),
+        (_, op) => ("AbsolutePath", filter_op(op), op == Some('!')),
+    };
+
+    let value = if field == "ext" {
+        format!(".{value}")
+    } else {
+        value.to_string()
+    };
+
+    let op = match (op, negate != negated) {
+        ("Is", false) => "Is".to_string(),
+        ("Is", true) => "IsNot".to_string(),
+        (op, false) => format!("Does{op}"),
+        (op, true) => format!("DoesNot{op}"),
+    };
+
+    let name = format!("{case}{subject}{op}");
+    let Ok(filter) = serde_json::from_value::<NodeFilter>(name.clone().into()) else {
+        bail!("unsupported filter: {name:?}")
+    };
+
+    Ok(NodeFilterApplicable::new(filter, value))
+}
+
+fn filter_op(op: Option<char>) -> &'static str {
+    match op {
+        None | Some('!') => "Is",
+        Some('^') => "StartWith",
+        Some('$') => "EndWith",
+        Some('*') => "Contain",
+        _ => "MatchRegex",
+    }
+}
+
+pub(crate) fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let pattern = glob
+        .chars()
+        .map(|c| match c {
+            '*' => "[^/]*".to_string(),
+            '?' => "[^/]".to_string(),
+            c => regex::escape(&c.to_string()),
+        })
+        .collect::<String>();
+    Ok(Regex::new(&format!("^{pattern}$"))?)
+}
+
+/// Complete the command name, the sorter name, or the path being typed.
+pub fn complete(line: &str, pwd: &str) -> Result<Option<String>> {
+    let (prefix, line) = match line.strip_prefix(':') {
+        Some(l) => (":", l),
+        None => ("", line),
+    };
+
+    let Some((head, word)) = line.rsplit_once(char::is_whitespace) else {
+        if line.starts_with('!') {
+            return Ok(None);
+        }
+        let names = COMMANDS.iter().copied().filter(|c| c.starts_with(line));
+        return Ok(common_prefix(line, names).map(|c| format!("{prefix}{c}")));
+    };
+
+    let completion = if head.split_whitespace().next() == Some("sort") {
+        let names = SORTERS
+            .iter()
+            .map(|(n, _)| *n)
+            .filter(|n| n.starts_with(word));
+        common_prefix(word, names)
+    } else if word.is_empty() {
+        None
+    } else {
+        explorer::try_complete_path(pwd, &expand_home(word))?
+    };
+
+    Ok(completion.map(|c| format!("{prefix}{head} {c}")))
+}
+
+fn common_prefix<'a>(
+    word: &str,
+    mut candidates: impl Iterator<Item = &'a str>,
+) -> Option<String> {
+    let first = candidates.next()?;
+    let mut prefix = first.to_string();
+    let mut count = 1;
+    for c in candidates {
+        count += 1;
+        while !c.starts_with(&prefix) {
+            prefix.pop();
+        }
+    }
+
+    if count == 1 {
+        Some(format!("{first} "))
+    } else if prefix.len() > word.len() {
+        Some(prefix)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommandLineHistory {
+    pub lines: Vec<String>,
+    pub loc: Option<usize>,
+}
+
+impl CommandLineHistory {
+    pub fn push(mut self, line: String) -> Self {
+        if !line.trim().is_empty() && self.lines.last() != Some(&line) {
+            self.lines.push(line);
+        }
+        self.loc = None;
+        self
+    }
+
+    pub fn previous(mut self) -> Self {
+        self.loc = match self.loc {
+            None => self.lines.len().checked_sub(1),
+            Some(loc) => Some(loc.saturating_sub(1)),
+        };
+        self
+    }
+
+    pub fn next(mut self) -> Self {
+        self.loc = self.loc.map(|l| l + 1).filter(|l| *l < self.lines.len());
+        self
+    }
+
+    pub fn peek(&self) -> Option<&String> {
+        self.loc.and_then(|l| self.lines.get(l))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command_line() {
+        assert_eq!(
+            CommandLine::parse(":cd /tmp").unwrap(),
+            CommandLine::ChangeDirectory("/tmp".into())
+        );
+
+        assert_eq!(
+            CommandLine::parse("sort size rev").unwrap(),
+            CommandLine::Sort(vec![NodeSorterApplicable {
+                sorter: NodeSorter::BySize,
+                reverse: true
+            }])
+        );
+        assert_eq!(
+            CommandLine::parse("sort ByCreated").unwrap(),
+            CommandLine::Sort(vec![NodeSorterApplicable {
+                sorter: NodeSorter::ByCreated,
+                reverse: false
+            }])
+        );
+        assert_eq!(
+            CommandLine::parse("sort").unwrap(),
+            CommandLine::Sort(vec![])
+        );
+
+        let filter = |s: &str| match CommandLine::parse(s).unwrap() {
+            CommandLine::Filter(Some(f)) => (f.filter, f.input),
+            c => panic!("not a filter: {c:?}"),
+        };
+        assert_eq!(
+            filter("filter ext=rs"),
+            (NodeFilter::RelativePathDoesEndWith, ".rs".into())
+        );
+        assert_eq!(
+            filter("filter !iext=rs"),
+            (NodeFilter::IRelativePathDoesNotEndWith, ".rs".into())
+        );
+        assert_eq!(
+            filter("filter name!=foo"),
+            (NodeFilter::RelativePathIsNot, "foo".into())
+        );
+        assert_eq!(
+            filter("filter !ipath*=foo"),
+            (NodeFilter::IAbsolutePathDoesNotContain, "foo".into())
+        );
+        assert_eq!(
+            filter("filter name~=^a.*"),
+            (NodeFilter::RelativePathDoesMatchRegex, "^a.*".into())
+        );
+
+        assert_eq!(
+            CommandLine::parse("!echo %s").unwrap(),
+            CommandLine::Shell("echo %s".into())
+        );
+        assert_eq!(CommandLine::parse("q").unwrap(), CommandLine::Quit);
+
+        assert!(CommandLine::parse("").is_err());
+        assert!(CommandLine::parse("foo").is_err());
+        assert!(CommandLine::parse("sort foo").is_err());
+        assert!(CommandLine::parse("filter size=1").is_err());
+        assert!(CommandLine::parse("filter ext^=rs").is_err());
+    }
+
+    #[test]
+    fn test_command_line_messages() {
+        let nodes = vec![
+            Node::new("/tmp".into(), "a.log".into()),
+            Node::new("/tmp".into(), "b.txt".into()),
+        ];
+
+        let msgs = CommandLine::parse("select *.log")
+            .unwrap()
+            .messages(&nodes, None);
+        assert_eq!(msgs, vec![ExternalMsg::SelectPath("/tmp/a.log".into())]);
+
+        let msgs = CommandLine::parse("!cat %s")
+            .unwrap()
+            .messages(&nodes, nodes.get(1));
+        assert_eq!(msgs, vec![ExternalMsg::BashExec("cat /tmp/b.txt".into())]);
+    }
+
+    #[test]
+    fn test_complete_command_line() {
+        assert_eq!(complete(":se", "/").unwrap(), Some(":select ".into()));
+        assert_eq!(complete("s", "/").unwrap(), None);
+        assert_eq!(complete("sort si", "/").unwrap(), Some("sort size ".into()));
+        assert_eq!(complete("sort m", "/").unwrap(), None);
+        assert_eq!(
+            complete("sort mo", "/").unwrap(),
+            Some("sort modified ".into())
+        );
+    }
+
+    #[test]
+    fn test_command_line_history() {
+        let hist = CommandLineHistory::default()
+            .push("cd /".into())
+            .push("q".into())
+            .push("q".into());
+        assert_eq!(hist.lines.len(), 2);
+
+        let hist = hist.previous();
+        assert_eq!(hist.peek().map(String::as_str), Some("q"));
+        let hist = hist.previous().previous();
+        assert_eq!(hist.peek().map(String::as_str), Some("cd /"));
+        let hist = hist.next().next();
+        assert_eq!(hist.peek(), None);
+    }
+}
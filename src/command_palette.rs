@@ -0,0 +1,193 @@
+use crate::app::ExternalMsg;
+use crate::config::{KeyBindings, ModesConfig};
+use crate::search::{PathItem, SearchAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The name of the builtin mode the command palette is opened in.
+pub const COMMAND_PALETTE_MODE: &str = "command_palette";
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PaletteItem {
+    pub mode: String,
+    pub keys: Vec<String>,
+    pub help: String,
+    pub messages: Vec<ExternalMsg>,
+}
+
+impl PaletteItem {
+    fn text(&self) -> String {
+        format!("{0} {1} {2}", self.help, self.mode, self.keys.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommandPalette {
+    pub items: Vec<PaletteItem>,
+    pub matches: Vec<usize>,
+    pub focus: usize,
+}
+
+impl CommandPalette {
+    /// List the key bindings with help text of the global key bindings, then
+    /// of the builtin modes, and then of the custom modes, sorted by mode
+    /// name. The keys mapped to the same action in a mode are listed together.
+    pub fn new(modes: &ModesConfig, global: &KeyBindings, read_only: bool) -> Self {
+        let mut builtin = modes
+            .builtin
+            .iter()
+            .filter(|(k, _)| *k != COMMAND_PALETTE_MODE)
+            .map(|(_, m)| m)
+            .collect::<Vec<_>>();
+        builtin.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut custom = modes.custom.values().collect::<Vec<_>>();
+        custom.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let global = ("global".to_string(), global.clone());
+        let bindings = std::iter::once(global).chain(
+            builtin
+                .into_iter()
+                .chain(custom)
+                .map(|m| (m.name.clone(), m.key_bindings.clone())),
+        );
+
+        let mut items: Vec<PaletteItem> = vec![];
+        for (mode, kb) in bindings {
+            let first = items.len();
+            for (key, action) in kb.sanitized(read_only).on_key {
+                let Some(help) = action.help else {
+                    continue;
+                };
+
+                let key = key.split_whitespace().collect::<Vec<&str>>().join(" ");
+                if let Some(item) = items[first..]
+                    .iter_mut()
+                    .find(|i| i.help == help && i.messages == action.messages)
+                {
+                    item.keys.push(key);
+                } else {
+                    items.push(PaletteItem {
+                        mode: mode.clone(),
+                        keys: vec![key],
+                        help,
+                        messages: action.messages,
+                    });
+                }
+            }
+        }
+
+        let matches = (0..items.len()).collect();
+        Self {
+            items,
+            matches,
+            focus: 0,
+        }
+    }
+
+    /// Fuzzy match the items against the pattern, best match first. An empty
+    /// pattern matches all the items in their original order.
+    pub fn search(mut self, pattern: &str) -> Self {
+        self.matches = if pattern.is_empty() {
+            (0..self.items.len()).collect()
+        } else {
+            let engine = SearchAlgorithm::Fuzzy.engine(pattern, false, None);
+            let mut ranked = self
+                .items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| {
+                    let text = Arc::new(PathItem::from(item.text()));
+                    engine.match_item(text).map(|res| (i, res.rank))
+                })
+                .collect::<Vec<_>>();
+            ranked.sort_by_key(|(_, r)| *r);
+            ranked.into_iter().map(|(i, _)| i).collect()
+        };
+        self.focus = 0;
+        self
+    }
+
+    pub fn focus_next(mut self) -> Self {
+        self.focus = (self.focus + 1).min(self.matches.len().saturating_sub(1));
+        self
+    }
+
+    pub fn focus_previous(mut self) -> Self {
+        self.focus = self.focus.saturating_sub(1);
+        self
+    }
+
+    pub fn matched_items(&self) -> impl Iterator<Item = &PaletteItem> {
+        self.matches.iter().filter_map(|i| self.items.get(*i))
+    }
+
+    pub fn focused_item(&self) -> Option<&PaletteItem> {
+        self.matches
+            .get(self.focus)
+            .and_then(|i| self.items.get(*i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Action, Mode};
+
+    fn action(help: &str, msg: ExternalMsg) -> Action {
+        Action {
+            help: Some(help.into()),
+            messages: vec![msg],
+        }
+    }
+
+    #[test]
+    fn test_command_palette() {
+        let mut kb = KeyBindings::default();
+        kb.on_key
+            .insert("j".into(), action("down", ExternalMsg::FocusNext));
+        kb.on_key
+            .insert("down".into(), action("down", ExternalMsg::FocusNext));
+        kb.on_key
+            .insert("q".into(), action("quit", ExternalMsg::Quit));
+        kb.on_key.insert(
+            "x".into(),
+            Action {
+                help: None,
+                messages: vec![ExternalMsg::Quit],
+            },
+        );
+
+        let mut modes = ModesConfig::default();
+        modes.builtin.insert(
+            "default".into(),
+            Mode {
+                name: "default".into(),
+                key_bindings: kb,
+                ..Default::default()
+            },
+        );
+        modes.builtin.insert(
+            COMMAND_PALETTE_MODE.into(),
+            Mode {
+                name: "command palette".into(),
+                ..Default::default()
+            },
+        );
+
+        let palette = CommandPalette::new(&modes, &KeyBindings::default(), false);
+        assert_eq!(palette.items.len(), 2);
+        assert_eq!(palette.items[0].keys, vec!["down", "j"]);
+        assert_eq!(palette.items[1].mode, "default");
+
+        let palette = palette.search("qt");
+        assert_eq!(palette.focused_item().unwrap().help, "quit");
+
+        let palette = palette.search("").focus_next().focus_next();
+        assert_eq!(palette.focus, 1);
+        assert_eq!(palette.matched_items().count(), 2);
+
+        let palette = palette.search("nothing like it");
+        assert_eq!(palette.focused_item(), None);
+    }
+}
@@ -3,6 +3,7 @@ use crate::app::HelpMenuLine;
 use crate::app::NodeFilter;
 use crate::app::NodeSorter;
 use crate::app::NodeSorterApplicable;
+use crate::input::MouseAction;
 use crate::node::Node;
 use crate::search::RankCriteria;
 use crate::search::SearchAlgorithm;
@@ -11,6 +12,7 @@ use crate::ui::BorderType;
 use crate::ui::Constraint;
 use crate::ui::Layout;
 use crate::ui::Style;
+use anyhow::{bail, Result};
 use indexmap::IndexSet;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -220,6 +222,12 @@ pub struct LogsConfig {
 
     #[serde(default)]
     pub error: UiElement,
+
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+
+    #[serde(default)]
+    pub write_to_file: bool,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -287,6 +295,30 @@ pub struct PanelUi {
 
     #[serde(default)]
     pub help_menu: PanelUiConfig,
+
+    #[serde(default)]
+    pub focused: PanelUiConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    #[default]
+    Paths,
+    Json,
+    Jsonl,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "paths" => Ok(Self::Paths),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => bail!("invalid output format: {s:?}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -313,6 +345,15 @@ pub struct GeneralConfig {
     #[serde(default)]
     pub enforce_bounded_index_navigation: bool,
 
+    #[serde(default)]
+    pub load_extended_metadata: bool,
+
+    #[serde(default)]
+    pub key_sequence_timeout_ms: u64,
+
+    #[serde(default)]
+    pub call_lua_async_timeout_ms: u64,
+
     #[serde(default)]
     pub prompt: UiElement,
 
@@ -358,9 +399,18 @@ pub struct GeneralConfig {
     #[serde(default)]
     pub start_fifo: Option<String>,
 
+    #[serde(default)]
+    pub start_socket: bool,
+
+    #[serde(default)]
+    pub output_format: OutputFormat,
+
     #[serde(default)]
     pub global_key_bindings: KeyBindings,
 
+    #[serde(default)]
+    pub panel_key_bindings: KeyBindings,
+
     #[serde(default)]
     pub paginated_scrolling: bool,
 
@@ -397,6 +447,9 @@ pub struct KeyBindings {
 
     #[serde(default)]
     pub default: Option<Action>,
+
+    #[serde(default)]
+    pub on_mouse: BTreeMap<MouseAction, Action>,
     // Checklist for adding new field:
     // - [ ] Update App::handle_key
     // - [ ] Update KeyBindings::sanitized
@@ -425,6 +478,11 @@ impl KeyBindings {
             self.on_navigation = self.on_navigation.and_then(|a| a.sanitized(read_only));
             self.on_function = self.on_function.and_then(|a| a.sanitized(read_only));
             self.default = self.default.and_then(|a| a.sanitized(read_only));
+            self.on_mouse = self
+                .on_mouse
+                .into_iter()
+                .filter_map(|(m, a)| a.sanitized(read_only).map(|a| (m, a)))
+                .collect();
         };
         self
     }
@@ -440,6 +498,7 @@ impl KeyBindings {
         self.on_navigation = other.on_navigation.or(self.on_navigation);
         self.on_function = other.on_function.or(self.on_function);
         self.default = other.default.or(self.default);
+        self.on_mouse.extend(other.on_mouse);
         self
     }
 }
@@ -511,9 +570,9 @@ impl Mode {
                                 }
                             })
                             .collect::<Vec<String>>();
-                        a.help
-                            .clone()
-                            .map(|h| HelpMenuLine::KeyMap(k.into(), remaps, h))
+                        // Key sequences are displayed as space separated keys.
+                        let k = k.split_whitespace().collect::<Vec<&str>>().join(" ");
+                        a.help.clone().map(|h| HelpMenuLine::KeyMap(k, remaps, h))
                     }))
                     .chain(
                         self.key_bindings
@@ -586,7 +645,12 @@ impl Mode {
                             .filter_map(|(k, mh)| {
                                 mh.map(|h| HelpMenuLine::KeyMap(k.into(), vec![], h))
                             }),
-                    );
+                    )
+                    .chain(self.key_bindings.on_mouse.iter().filter_map(|(m, a)| {
+                        a.help
+                            .clone()
+                            .map(|h| HelpMenuLine::KeyMap(format!("[{m}]"), vec![], h))
+                    }));
 
                 let mut remapped = HashSet::new();
                 let mut result = vec![];
@@ -696,6 +760,25 @@ pub struct Config {
 
     #[serde(default)]
     pub modes: ModesConfig,
+
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+}
+
+/// The `setup` argument, which may contain functions, is read from Lua.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    pub name: String,
+
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[serde(default)]
+    pub git: Option<String>,
+
+    #[serde(default)]
+    pub rev: Option<String>,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
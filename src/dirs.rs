@@ -15,6 +15,14 @@ pub fn config_dir() -> Option<PathBuf> {
     BASE_DIRS.get_config_home()
 }
 
+pub fn data_dir() -> Option<PathBuf> {
+    BASE_DIRS.get_data_home()
+}
+
+pub fn state_dir() -> Option<PathBuf> {
+    BASE_DIRS.get_state_home()
+}
+
 pub fn runtime_dir() -> PathBuf {
     let Some(dir) = BASE_DIRS.get_runtime_directory().ok() else {
         return env::temp_dir();
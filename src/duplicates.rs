@@ -0,0 +1,358 @@
+use crate::app::{DirectoryBuffer, ExternalMsg, InternalMsg, MsgIn, Node, Task};
+use anyhow::{Error, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+/// Number of bytes read from the beginning of each file to compute the
+/// partial hash.
+const PARTIAL_HASH_SIZE: u64 = 4096;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub nodes: Vec<Node>,
+    pub expanded: bool,
+}
+
+impl DuplicateGroup {
+    fn contains(&self, path: &str) -> bool {
+        self.nodes.iter().any(|n| n.absolute_path == path)
+    }
+
+    fn visible_nodes(&self) -> &[Node] {
+        if self.expanded {
+            &self.nodes
+        } else {
+            &self.nodes[..self.nodes.len().min(1)]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Duplicates {
+    pub root: String,
+    pub groups: Vec<DuplicateGroup>,
+}
+
+impl Duplicates {
+    /// The nodes to list. A collapsed group only shows its first node.
+    pub fn nodes(&self) -> Vec<Node> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.visible_nodes().to_vec())
+            .collect()
+    }
+
+    /// All the nodes, except the first one of each group.
+    pub fn redundant_nodes(&self) -> Vec<Node> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.nodes.iter().skip(1).cloned())
+            .collect()
+    }
+
+    pub fn toggle_group(mut self, path: &str) -> Self {
+        if let Some(group) = self.groups.iter_mut().find(|g| g.contains(path)) {
+            group.expanded = !group.expanded;
+        }
+        self
+    }
+
+    /// Drop the nodes that no longer exist, along with the groups that are no
+    /// longer duplicates.
+    pub fn refreshed(mut self) -> Self {
+        for group in self.groups.iter_mut() {
+            group
+                .nodes
+                .retain(|n| PathBuf::from(&n.absolute_path).symlink_metadata().is_ok());
+        }
+        self.groups.retain(|g| g.nodes.len() > 1);
+        self
+    }
+
+    pub fn to_directory_buffer(&self, focus: usize) -> DirectoryBuffer {
+        let nodes = self.nodes();
+        let focus = focus.min(nodes.len().saturating_sub(1));
+        DirectoryBuffer::new(self.root.clone(), nodes, focus)
+    }
+}
+
+fn walk(root: &Path) -> Vec<(u64, PathBuf)> {
+    let mut files = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        // Unreadable directories are skipped silently.
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(meta) = entry.path().symlink_metadata() else {
+                continue;
+            };
+
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            } else if meta.is_file() && meta.len() > 0 {
+                files.push((meta.len(), entry.path()));
+            }
+        }
+    }
+
+    files
+}
+
+fn hash_file(path: &Path, limit: Option<u64>) -> Option<u64> {
+    let file = fs::File::open(path).ok()?;
+    let mut reader: Box<dyn Read> = match limit {
+        Some(l) => Box::new(file.take(l)),
+        None => Box::new(file),
+    };
+
+    let mut hasher = DefaultHasher::new();
+    let mut buf = [0; 8192];
+    loop {
+        match reader.read(&mut buf).ok()? {
+            0 => break,
+            n => hasher.write(&buf[..n]),
+        }
+    }
+
+    Some(hasher.finish())
+}
+
+/// Compare the content of two files byte by byte.
+fn same_content(a: &Path, b: &Path) -> bool {
+    let (Ok(mut a), Ok(mut b)) = (fs::File::open(a), fs::File::open(b)) else {
+        return false;
+    };
+
+    let mut buf_a = [0; 8192];
+    let mut buf_b = [0; 8192];
+    loop {
+        let Ok(n) = a.read(&mut buf_a) else {
+            return false;
+        };
+        if n == 0 {
+            return b.read(&mut buf_b).map(|m| m == 0).unwrap_or(false);
+        }
+        if b.read_exact(&mut buf_b[..n]).is_err() || buf_a[..n] != buf_b[..n] {
+            return false;
+        }
+    }
+}
+
+/// Split a group of candidates into groups of files with identical content,
+/// so that hash collisions are never reported as duplicates.
+fn confirm(groups: Vec<Vec<PathBuf>>) -> Vec<Vec<PathBuf>> {
+    groups
+        .into_par_iter()
+        .flat_map(|group| {
+            let mut confirmed: Vec<Vec<PathBuf>> = vec![];
+            for path in group {
+                match confirmed.iter_mut().find(|g| same_content(&g[0], &path)) {
+                    Some(g) => g.push(path),
+                    None => confirmed.push(vec![path]),
+                }
+            }
+            confirmed
+                .into_iter()
+                .filter(|g| g.len() > 1)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn regroup<F>(groups: Vec<Vec<PathBuf>>, f: F) -> Vec<Vec<PathBuf>>
+where
+    F: Fn(&Path) -> Option<u64> + Sync,
+{
+    groups
+        .into_par_iter()
+        .flat_map(|group| {
+            let mut by_hash: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+            for path in group {
+                if let Some(hash) = f(&path) {
+                    by_hash.entry(hash).or_default().push(path);
+                }
+            }
+            by_hash
+                .into_values()
+                .filter(|g| g.len() > 1)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub fn find(root: PathBuf) -> Result<Duplicates> {
+    fs::read_dir(&root)?;
+
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (size, path) in walk(&root) {
+        by_size.entry(size).or_default().push(path);
+    }
+
+    let candidates = by_size.into_values().filter(|g| g.len() > 1).collect();
+    let candidates = regroup(candidates, |p| hash_file(p, Some(PARTIAL_HASH_SIZE)));
+    let candidates = regroup(candidates, |p| {
+        // Small files are already fully hashed.
+        if p.symlink_metadata().ok()?.len() > PARTIAL_HASH_SIZE {
+            hash_file(p, None)
+        } else {
+            Some(0)
+        }
+    });
+    let candidates = confirm(candidates);
+
+    let root_str = root.to_string_lossy().to_string();
+    let mut groups: Vec<DuplicateGroup> = candidates
+        .into_par_iter()
+        .map(|mut paths| {
+            paths.sort();
+            let nodes: Vec<Node> = paths
+                .iter()
+                .map(|p| {
+                    let relative_path = p
+                        .strip_prefix(&root)
+                        .unwrap_or(p)
+                        .to_string_lossy()
+                        .to_string();
+                    Node::new(root_str.clone(), relative_path)
+                })
+                .collect();
+            let size = nodes.first().map(|n| n.size).unwrap_or_default();
+            DuplicateGroup {
+                size,
+                nodes,
+                expanded: false,
+            }
+        })
+        .collect();
+
+    groups.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.nodes[0].relative_path.cmp(&b.nodes[0].relative_path))
+    });
+
+    Ok(Duplicates {
+        root: root_str,
+        groups,
+    })
+}
+
+pub(crate) fn find_async(root: PathBuf, tx_msg_in: Sender<Task>) {
+    thread::spawn(move || {
+        find(root.clone())
+            .and_then(|dups| {
+                tx_msg_in
+                    .send(Task::new(
+                        MsgIn::Internal(InternalMsg::SetDuplicates(dups)),
+                        None,
+                    ))
+                    .map_err(Error::new)
+            })
+            .unwrap_or_else(|e| {
+                tx_msg_in
+                    .send(Task::new(
+                        MsgIn::External(ExternalMsg::LogError(format!(
+                            "could not find duplicates in {root:?}: {e}"
+                        ))),
+                        None,
+                    ))
+                    .unwrap_or_default(); // Let's not panic if xplr closes.
+            })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_confirm() {
+        let tmp = TempDir::new("confirm").unwrap();
+        let root = tmp.path();
+
+        fs::write(root.join("a"), "hello").unwrap();
+        fs::write(root.join("b"), "hello").unwrap();
+        fs::write(root.join("c"), "world").unwrap();
+        fs::write(root.join("d"), "hello world").unwrap();
+
+        // Pretend all of them collided on the hash.
+        let candidates = vec![vec![
+            root.join("a"),
+            root.join("c"),
+            root.join("b"),
+            root.join("d"),
+        ]];
+        let confirmed = confirm(candidates);
+
+        assert_eq!(confirmed, vec![vec![root.join("a"), root.join("b")]]);
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let tmp = TempDir::new("find_duplicates").unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("sub")).unwrap();
+
+        let big = "x".repeat(PARTIAL_HASH_SIZE as usize * 2);
+        let big_diff = format!("{big}y");
+        let big_same_prefix = format!("{}z", &big[..big.len() - 1]);
+
+        fs::write(root.join("a"), "hello").unwrap();
+        fs::write(root.join("sub/b"), "hello").unwrap();
+        fs::write(root.join("c"), "world").unwrap();
+        fs::write(root.join("d"), &big).unwrap();
+        fs::write(root.join("sub/e"), &big).unwrap();
+        fs::write(root.join("f"), &big_diff).unwrap();
+        fs::write(root.join("g"), &big_same_prefix).unwrap();
+        fs::write(root.join("empty1"), "").unwrap();
+        fs::write(root.join("empty2"), "").unwrap();
+
+        let dups = find(root.to_path_buf()).unwrap();
+
+        let groups: Vec<Vec<String>> = dups
+            .groups
+            .iter()
+            .map(|g| g.nodes.iter().map(|n| n.relative_path.clone()).collect())
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![
+                vec!["d".to_string(), "sub/e".to_string()],
+                vec!["a".to_string(), "sub/b".to_string()],
+            ]
+        );
+
+        let names = |nodes: Vec<Node>| {
+            nodes
+                .into_iter()
+                .map(|n| n.relative_path)
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(names(dups.nodes()), vec!["d", "a"]);
+        assert_eq!(names(dups.redundant_nodes()), vec!["sub/e", "sub/b"]);
+
+        let path = dups.groups[1].nodes[0].absolute_path.clone();
+        let dups = dups.toggle_group(&path);
+        assert_eq!(names(dups.nodes()), vec!["d", "a", "sub/b"]);
+
+        // Everything is gone now
+        fs::remove_dir_all(root).unwrap();
+        assert!(dups.refreshed().groups.is_empty());
+    }
+}
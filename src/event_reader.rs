@@ -1,12 +1,14 @@
 use crate::app::Task;
 use crate::app::{ExternalMsg, InternalMsg, MsgIn};
-use crate::input::Key;
+use crate::input::{Key, MouseAction, MouseEvent};
 use anyhow::Error;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
-use std::time::Duration;
-use tui::crossterm::event::{self, Event, MouseEventKind};
+use std::time::{Duration, Instant};
+use tui::crossterm::event::{self, Event};
+
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
 
 pub(crate) struct EventReader {
     task_sender: Sender<Task>,
@@ -45,6 +47,7 @@ fn keep_reading(
     rx_stopper: Receiver<bool>,
     tx_ack: Sender<()>,
 ) {
+    let mut last_click: Option<(Instant, u16, u16)> = None;
     loop {
         if rx_stopper.try_recv().unwrap_or(false) {
             tx_ack.send(()).unwrap();
@@ -63,17 +66,27 @@ fn keep_reading(
                         .map_err(Error::new)
                 }
 
-                Ok(Event::Mouse(evt)) => match evt.kind {
-                    MouseEventKind::ScrollUp => {
-                        let msg = MsgIn::External(ExternalMsg::FocusPrevious);
-                        tx_msg_in.send(Task::new(msg, None)).map_err(Error::new)
-                    }
+                Ok(Event::Mouse(evt)) => match MouseEvent::from_event(evt) {
+                    Some(mut evt) => {
+                        if evt.action == MouseAction::LeftClick {
+                            let now = Instant::now();
+                            let is_double = last_click.is_some_and(|(t, c, r)| {
+                                now.duration_since(t) <= DOUBLE_CLICK_INTERVAL
+                                    && (c, r) == (evt.column, evt.row)
+                            });
+
+                            if is_double {
+                                evt.action = MouseAction::DoubleClick;
+                                last_click = None;
+                            } else {
+                                last_click = Some((now, evt.column, evt.row));
+                            }
+                        }
 
-                    MouseEventKind::ScrollDown => {
-                        let msg = MsgIn::External(ExternalMsg::FocusNext);
+                        let msg = MsgIn::Internal(InternalMsg::HandleMouse(evt));
                         tx_msg_in.send(Task::new(msg, None)).map_err(Error::new)
                     }
-                    _ => Ok(()),
+                    None => Ok(()),
                 },
 
                 Ok(Event::Resize(_, _)) => {
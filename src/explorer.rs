@@ -22,7 +22,14 @@ pub fn explore(parent: &PathBuf, config: &ExplorerConfig) -> Result<Vec<Node>> {
             })
         })
         .map(|name| Node::new(parent.to_string_lossy().to_string(), name))
-        .filter(|n| config.filter(n));
+        .filter(|n| config.filter(n))
+        .map(|n| {
+            if config.load_extended_metadata {
+                n.with_extended_metadata()
+            } else {
+                n
+            }
+        });
 
     let mut nodes = if let Some(searcher) = config.searcher.as_ref() {
         searcher.search(nodes)
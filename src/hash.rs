@@ -0,0 +1,447 @@
+use crate::app::{ExternalMsg, InternalMsg, MsgIn, Node, Task};
+use anyhow::{bail, Result};
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha1,
+    Md5,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('-', "").as_str() {
+            "sha256" => Some(Self::Sha256),
+            "sha1" => Some(Self::Sha1),
+            "md5" => Some(Self::Md5),
+            "blake3" | "b3" => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+
+    /// Guess the algorithm from the name of a checksum file, e.g.
+    /// `SHA256SUMS`, `archive.tar.gz.md5` or `B3SUMS`.
+    fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.contains("sha256") {
+            Some(Self::Sha256)
+        } else if name.contains("sha1") {
+            Some(Self::Sha1)
+        } else if name.contains("md5") {
+            Some(Self::Md5)
+        } else if name.contains("blake3") || name.contains("b3") {
+            Some(Self::Blake3)
+        } else {
+            None
+        }
+    }
+
+    /// Guess the algorithm from the length of a hex digest. Blake3 and SHA256
+    /// digests have the same length, in which case SHA256 is assumed.
+    fn from_digest(digest: &str) -> Option<Self> {
+        match digest.len() {
+            64 => Some(Self::Sha256),
+            40 => Some(Self::Sha1),
+            32 => Some(Self::Md5),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Sha256 => "sha256",
+            Self::Sha1 => "sha1",
+            Self::Md5 => "md5",
+            Self::Blake3 => "blake3",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HashOptions {
+    pub algo: HashAlgorithm,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumStatus {
+    Ok,
+    Mismatch,
+    Missing,
+}
+
+/// The result of hashing a file, or verifying its checksum.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeHash {
+    pub absolute_path: String,
+    pub size: u64,
+    pub last_modified: Option<u128>,
+    pub algo: HashAlgorithm,
+    pub digest: Option<String>,
+    pub checksum: Option<ChecksumStatus>,
+
+    /// Why the file couldn't be hashed, if it couldn't.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl NodeHash {
+    fn failed(
+        path: &Path,
+        algo: HashAlgorithm,
+        checksum: Option<ChecksumStatus>,
+    ) -> Self {
+        Self {
+            absolute_path: path.to_string_lossy().to_string(),
+            size: 0,
+            last_modified: None,
+            algo,
+            digest: None,
+            checksum,
+            error: None,
+        }
+    }
+}
+
+/// The hashes known for a file, as long as it remains unmodified.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeHashes {
+    pub size: u64,
+    pub last_modified: Option<u128>,
+    pub digests: BTreeMap<HashAlgorithm, String>,
+    pub checksum: Option<ChecksumStatus>,
+}
+
+impl NodeHashes {
+    pub fn is_fresh(&self, node: &Node) -> bool {
+        self.size == node.size && self.last_modified == node.last_modified
+    }
+
+    pub fn update(&mut self, hash: NodeHash) {
+        if self.size != hash.size || self.last_modified != hash.last_modified {
+            *self = Self {
+                size: hash.size,
+                last_modified: hash.last_modified,
+                ..Default::default()
+            };
+        }
+
+        if let Some(digest) = hash.digest {
+            self.digests.insert(hash.algo, digest);
+        }
+
+        if hash.checksum.is_some() {
+            self.checksum = hash.checksum;
+        }
+    }
+}
+
+fn digest<D: Digest + io::Write>(mut file: fs::File) -> Result<String> {
+    let mut hasher = D::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+pub fn hash_file(path: &Path, algo: HashAlgorithm) -> Result<String> {
+    let file = fs::File::open(path)?;
+    match algo {
+        HashAlgorithm::Sha256 => digest::<Sha256>(file),
+        HashAlgorithm::Sha1 => digest::<Sha1>(file),
+        HashAlgorithm::Md5 => digest::<Md5>(file),
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(file)?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+pub fn hash_node(path: &Path, algo: HashAlgorithm) -> Result<NodeHash> {
+    let meta = path.symlink_metadata()?;
+    if !path.is_file() {
+        bail!("not a file: {:?}", path.to_string_lossy())
+    }
+
+    let digest = hash_file(path, algo)?;
+
+    Ok(NodeHash {
+        absolute_path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        last_modified: meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos()),
+        algo,
+        digest: Some(digest),
+        checksum: None,
+        error: None,
+    })
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct ChecksumEntry {
+    algo: HashAlgorithm,
+    digest: String,
+    path: PathBuf,
+}
+
+/// Parse a line in either the GNU (`<digest>  <path>`) or the BSD
+/// (`SHA256 (<path>) = <digest>`) format.
+fn parse_checksum_line(
+    line: &str,
+    default_algo: Option<HashAlgorithm>,
+) -> Option<ChecksumEntry> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    if let Some((left, digest)) = line.rsplit_once(") = ") {
+        let (name, path) = left.split_once(" (")?;
+        return Some(ChecksumEntry {
+            algo: HashAlgorithm::from_name(name)?,
+            digest: digest.trim().to_lowercase(),
+            path: path.into(),
+        });
+    }
+
+    let (digest, path) = line.split_once(' ')?;
+    let path = path
+        .strip_prefix(' ')
+        .or_else(|| path.strip_prefix('*'))
+        .unwrap_or(path);
+
+    if path.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(ChecksumEntry {
+        algo: default_algo.or_else(|| HashAlgorithm::from_digest(digest))?,
+        digest: digest.to_lowercase(),
+        path: path.into(),
+    })
+}
+
+fn verify_checksum_entry(entry: ChecksumEntry) -> NodeHash {
+    match hash_node(&entry.path, entry.algo) {
+        Ok(mut hash) => {
+            hash.checksum = if hash.digest.as_ref() == Some(&entry.digest) {
+                Some(ChecksumStatus::Ok)
+            } else {
+                Some(ChecksumStatus::Mismatch)
+            };
+            hash
+        }
+        Err(e) if is_not_found(&e) => {
+            NodeHash::failed(&entry.path, entry.algo, Some(ChecksumStatus::Missing))
+        }
+        Err(e) => NodeHash {
+            error: Some(e.to_string()),
+            ..NodeHash::failed(&entry.path, entry.algo, None)
+        },
+    }
+}
+
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+/// Verify each of the files listed in the checksum file. Relative paths are
+/// resolved relative to the directory of the checksum file.
+pub fn verify_checksum_file(path: &Path) -> Result<Vec<NodeHash>> {
+    let content = fs::read_to_string(path)?;
+    let parent = path.parent().map(PathBuf::from).unwrap_or_default();
+    let default_algo = path
+        .file_name()
+        .and_then(|n| HashAlgorithm::from_file_name(&n.to_string_lossy()));
+
+    let hashes = content
+        .lines()
+        .filter_map(|l| parse_checksum_line(l, default_algo))
+        .map(|e| ChecksumEntry {
+            path: parent.join(e.path),
+            ..e
+        })
+        .map(verify_checksum_entry)
+        .collect::<Vec<_>>();
+
+    if hashes.is_empty() {
+        bail!("no checksum found in {:?}", path.to_string_lossy())
+    }
+
+    Ok(hashes)
+}
+
+fn send_hashes(tx_msg_in: &Sender<Task>, hashes: Vec<NodeHash>) {
+    tx_msg_in
+        .send(Task::new(
+            MsgIn::Internal(InternalMsg::SetNodeHashes(hashes)),
+            None,
+        ))
+        .unwrap_or_default(); // Let's not panic if xplr closes.
+}
+
+fn send_error(tx_msg_in: &Sender<Task>, err: String) {
+    tx_msg_in
+        .send(Task::new(MsgIn::External(ExternalMsg::LogError(err)), None))
+        .unwrap_or_default(); // Let's not panic if xplr closes.
+}
+
+pub(crate) fn hash_async(
+    paths: Vec<PathBuf>,
+    algo: HashAlgorithm,
+    tx_msg_in: Sender<Task>,
+) {
+    thread::spawn(move || {
+        let hashes = paths
+            .into_iter()
+            .map(|path| {
+                hash_node(&path, algo).unwrap_or_else(|e| NodeHash {
+                    error: Some(e.to_string()),
+                    ..NodeHash::failed(&path, algo, None)
+                })
+            })
+            .collect();
+        send_hashes(&tx_msg_in, hashes);
+    });
+}
+
+pub(crate) fn verify_checksum_file_async(path: PathBuf, tx_msg_in: Sender<Task>) {
+    thread::spawn(move || match verify_checksum_file(&path) {
+        Ok(hashes) => send_hashes(&tx_msg_in, hashes),
+        Err(e) => send_error(
+            &tx_msg_in,
+            format!("could not verify {:?}: {e}", path.to_string_lossy()),
+        ),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_hash_file() {
+        let dir = TempDir::new("hash_file").unwrap();
+        let path = dir.path().join("hello.txt");
+        fs::write(&path, "hello\n").unwrap();
+
+        let hash = |algo| hash_file(&path, algo).unwrap();
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Sha1),
+            "f572d396fae9206628714fb2ce00f72e94f2258f"
+        );
+        assert_eq!(hash(HashAlgorithm::Md5), "b1946ac92492d2347c6235b4d2611184");
+        assert_eq!(
+            hash(HashAlgorithm::Blake3),
+            "8e4c7c1b99dbfd50e7a95185fead5ee1448fa904a2fdd778eaf5f2dbfd629a99"
+        );
+    }
+
+    #[test]
+    fn test_parse_checksum_line() {
+        let sha256 = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+        assert_eq!(
+            parse_checksum_line(&format!("{sha256}  foo bar.txt"), None),
+            Some(ChecksumEntry {
+                algo: HashAlgorithm::Sha256,
+                digest: sha256.into(),
+                path: "foo bar.txt".into(),
+            })
+        );
+
+        assert_eq!(
+            parse_checksum_line(
+                &format!("{sha256} *foo.bin"),
+                Some(HashAlgorithm::Blake3)
+            ),
+            Some(ChecksumEntry {
+                algo: HashAlgorithm::Blake3,
+                digest: sha256.into(),
+                path: "foo.bin".into(),
+            })
+        );
+
+        assert_eq!(
+            parse_checksum_line("MD5 (foo) = B1946AC92492D2347C6235B4D2611184", None),
+            Some(ChecksumEntry {
+                algo: HashAlgorithm::Md5,
+                digest: "b1946ac92492d2347c6235b4d2611184".into(),
+                path: "foo".into(),
+            })
+        );
+
+        assert_eq!(parse_checksum_line("# comment", None), None);
+        assert_eq!(parse_checksum_line("", None), None);
+        assert_eq!(parse_checksum_line("xyz  foo", None), None);
+    }
+
+    #[test]
+    fn test_verify_checksum_file() {
+        let tmp = TempDir::new("verify_checksum_file").unwrap();
+        let dir = tmp.path();
+        fs::write(dir.join("a"), "hello\n").unwrap();
+        fs::write(dir.join("b"), "world\n").unwrap();
+        fs::create_dir(dir.join("d")).unwrap();
+        fs::write(
+            dir.join("SHA256SUMS"),
+            [
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  a",
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  b",
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  c",
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  d",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let hashes = verify_checksum_file(&dir.join("SHA256SUMS")).unwrap();
+        let statuses = hashes.iter().map(|h| h.checksum).collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            vec![
+                Some(ChecksumStatus::Ok),
+                Some(ChecksumStatus::Mismatch),
+                Some(ChecksumStatus::Missing),
+                None,
+            ]
+        );
+
+        // Only a file that doesn't exist is missing, the other errors are
+        // reported.
+        assert_eq!(hashes[2].error, None);
+        assert!(hashes[3].error.as_ref().unwrap().contains("not a file"));
+    }
+}
@@ -52,6 +52,7 @@ local xplr = xplr -- The globally exposed configuration to be overridden.
 -- * [xplr.config.node_types](https://xplr.dev/en/node_types)
 -- * [xplr.config.layouts](https://xplr.dev/en/layouts)
 -- * [xplr.config.modes](https://xplr.dev/en/modes)
+-- * [xplr.config.plugins](https://xplr.dev/en/configuration#plugins)
 
 -- ### General Configuration --------------------------------------------------
 --
@@ -64,7 +65,8 @@ local xplr = xplr -- The globally exposed configuration to be overridden.
 -- Type: boolean
 xplr.config.general.disable_debug_error_mode = false
 
--- Set it to `true` if you want to enable mouse scrolling.
+-- Set it to `true` if you want to enable mouse scrolling, clicking and
+-- dragging. See `on_mouse` in [Key Bindings](https://xplr.dev/en/configure-key-bindings#on_mouse).
 --
 -- Type: boolean
 xplr.config.general.enable_mouse = false
@@ -111,6 +113,26 @@ xplr.config.general.scroll_padding = 5
 -- Type: boolean
 xplr.config.general.enforce_bounded_index_navigation = false
 
+-- Set it to `true` to load the extended attributes, POSIX ACL entries and file
+-- capabilities of the listed nodes. It's disabled by default as it requires
+-- a few extra system calls per node.
+--
+-- Type: boolean
+xplr.config.general.load_extended_metadata = false
+
+-- The time in milliseconds to wait for the next key of a key sequence, such
+-- as `g g`, before handling the pending keys on their own. Set it to `0` to
+-- wait indefinitely.
+--
+-- Type: integer
+xplr.config.general.key_sequence_timeout_ms = 1000
+
+-- The time in milliseconds after which a function called with `CallLuaAsync`
+-- is interrupted and an error is logged. Set it to `0` to never interrupt it.
+--
+-- Type: integer
+xplr.config.general.call_lua_async_timeout_ms = 10000
+
 -- This is the shape of the prompt for the input buffer.
 --
 -- Type: nullable string
@@ -161,6 +183,20 @@ xplr.config.general.logs.error.format = "ERROR"
 -- Type: [Style](https://xplr.dev/en/style)
 xplr.config.general.logs.error.style = { fg = "Red" }
 
+-- The maximum number of logs to keep in memory. The oldest logs are dropped
+-- when it's exceeded. Set it to `nil` to keep all the logs.
+--
+-- Type: nullable integer
+xplr.config.general.logs.max_entries = 1000
+
+-- Set it to `true` to also append the logs to `$XDG_STATE_HOME/xplr/xplr.log`,
+-- prefixed with the process ID, for debugging after xplr exits.
+-- The file is written in the background, and moved to `xplr.log.1` once it
+-- exceeds 1 MiB.
+--
+-- Type: boolean
+xplr.config.general.logs.write_to_file = false
+
 -- Columns to display in the table header.
 --
 -- Type: nullable list of tables with the following fields:
@@ -416,6 +452,8 @@ xplr.config.general.sort_and_filter_ui.sorter_identifiers = {
   BySize = { format = "size", style = {} },
   ByCreated = { format = "created", style = {} },
   ByLastModified = { format = "modified", style = {} },
+  ByUserName = { format = "user", style = {} },
+  ByGroupName = { format = "group", style = {} },
   ByCanonicalAbsolutePath = { format = "[c]abs", style = {} },
   ByCanonicalExtension = { format = "[c]ext", style = {} },
   ByCanonicalIsDir = { format = "[c]dir", style = {} },
@@ -692,6 +730,36 @@ xplr.config.general.panel_ui.sort_and_filter.border_type = nil
 -- Type: [Style](https://xplr.dev/en/style)
 xplr.config.general.panel_ui.sort_and_filter.border_style = {}
 
+-- The content for the focused panel title, overriding the panel's own.
+--
+-- Type: nullable string
+xplr.config.general.panel_ui.focused.title.format = nil
+
+-- Style of the focused panel title, extending the panel's own.
+--
+-- Type: [Style](https://xplr.dev/en/style)
+xplr.config.general.panel_ui.focused.title.style = {}
+
+-- Style of the focused panel, extending the panel's own.
+--
+-- Type: [Style](https://xplr.dev/en/style)
+xplr.config.general.panel_ui.focused.style = {}
+
+-- Defines where to show borders for the focused panel.
+--
+-- Type: nullable list of [Border](https://xplr.dev/en/borders#border)
+xplr.config.general.panel_ui.focused.borders = nil
+
+-- Type of the borders for the focused panel.
+--
+-- Type: nullable [Border Type](https://xplr.dev/en/borders#border-type)
+xplr.config.general.panel_ui.focused.border_type = "Double"
+
+-- Style of the focused panel borders, extending the panel's own.
+--
+-- Type: [Style](https://xplr.dev/en/style)
+xplr.config.general.panel_ui.focused.border_style = {}
+
 -- Initial group if sorters applied to the nodes list in the table.
 --
 -- Type: nullable list of [Node Sorter](https://xplr.dev/en/sorting#node-sorter-applicable)
@@ -720,6 +788,22 @@ xplr.config.general.initial_layout = "default"
 -- Type: nullable string
 xplr.config.general.start_fifo = nil
 
+-- Set it to `true` to listen on a Unix domain socket in the session path when
+-- xplr loads. Scripts and editors can send messages and queries to it to drive
+-- the running xplr, see [Socket](https://xplr.dev/en/environment-variables-and-pipes#socket).
+--
+-- Type: boolean
+xplr.config.general.start_socket = false
+
+-- The format of the paths written to the selection, result and directory
+-- nodes pipes, and printed when quitting with `PrintResultAndQuit`,
+-- `PrintSelectionAndQuit` or `PrintFocusPathAndQuit`. Use "Json" or "Jsonl" to
+-- get the full node objects, as a JSON array or as one JSON object per line.
+-- It can be overridden with `--output-format json|jsonl|paths`.
+--
+-- Type: one of "Paths", "Json" or "Jsonl"
+xplr.config.general.output_format = "Paths"
+
 -- Use it to define a set of key bindings that are available by default in
 -- every [mode](https://xplr.dev/en/mode). They can be overwritten.
 --
@@ -748,8 +832,96 @@ xplr.config.general.global_key_bindings = {
       },
     },
   },
+  on_mouse = {
+    scroll_up = {
+      messages = {
+        "FocusPrevious",
+      },
+    },
+    scroll_down = {
+      messages = {
+        "FocusNext",
+      },
+    },
+  },
 }
 
+-- Use it to define a set of key bindings that are available, on top of the
+-- current mode's, while a panel other than the table is focused, e.g. via
+-- `CyclePanelFocus`. They can be overwritten.
+--
+-- Type: [Key Bindings](https://xplr.dev/en/configure-key-bindings#key-bindings)
+xplr.config.general.panel_key_bindings = {
+  on_key = {
+    ["down"] = {
+      help = "panel down",
+      messages = {
+        "FocusNextPanelRow",
+      },
+    },
+    ["up"] = {
+      help = "panel up",
+      messages = {
+        "FocusPreviousPanelRow",
+      },
+    },
+    ["page-down"] = {
+      help = "panel page down",
+      messages = {
+        { Repeat = { 10, { "FocusNextPanelRow" } } },
+      },
+    },
+    ["page-up"] = {
+      help = "panel page up",
+      messages = {
+        { Repeat = { 10, { "FocusPreviousPanelRow" } } },
+      },
+    },
+    ["enter"] = {
+      help = "activate panel row",
+      messages = {
+        "ActivatePanelRow",
+      },
+    },
+    ["esc"] = {
+      help = "unfocus panel",
+      messages = {
+        "UnfocusPanel",
+      },
+    },
+    ["tab"] = {
+      help = "next panel",
+      messages = {
+        "CyclePanelFocus",
+      },
+    },
+    ["/"] = {
+      help = "search panel",
+      messages = {
+        { SwitchModeBuiltin = "search_panel" },
+        { SetInputBuffer = "" },
+      },
+    },
+    ["n"] = {
+      help = "next match",
+      messages = {
+        "SearchPanelNext",
+      },
+    },
+    ["N"] = {
+      help = "previous match",
+      messages = {
+        "SearchPanelPrevious",
+      },
+    },
+  },
+}
+
+xplr.config.general.panel_key_bindings.on_key["j"] =
+  xplr.config.general.panel_key_bindings.on_key["down"]
+xplr.config.general.panel_key_bindings.on_key["k"] =
+  xplr.config.general.panel_key_bindings.on_key["up"]
+
 -- ### Node Types -------------------------------------------------------------
 --
 -- This section defines how to deal with different kinds of nodes (files,
@@ -1055,6 +1227,24 @@ xplr.config.layouts.builtin.no_help_no_selection = {
   },
 }
 
+-- The layout of the log viewer
+--
+-- Type: [Layout](https://xplr.dev/en/layout)
+xplr.config.layouts.builtin.logs = {
+  Vertical = {
+    config = {
+      constraints = {
+        { Min = 1 },
+        { Length = 3 },
+      },
+    },
+    splits = {
+      "Logs",
+      "InputAndLogs",
+    },
+  },
+}
+
 -- This is where you can define custom layouts
 --
 -- Type: mapping of the following key-value pairs:
@@ -1112,10 +1302,10 @@ xplr.config.modes.builtin.default = {
         },
       },
       ["G"] = {
-        help = "go to bottom",
+        help = "go to bottom/[count]",
         messages = {
           "PopMode",
-          "FocusLast",
+          { CallLuaSilently = "builtin.focus_last_or_count" },
         },
       },
       ["ctrl-a"] = {
@@ -1135,25 +1325,45 @@ xplr.config.modes.builtin.default = {
       ["ctrl-i"] = {
         help = "next visited path",
         messages = {
-          "NextVisitedPath",
+          { Repeat = { 1, { "NextVisitedPath" } } },
         },
       },
       ["ctrl-o"] = {
         help = "last visited path",
         messages = {
-          "LastVisitedPath",
+          { Repeat = { 1, { "LastVisitedPath" } } },
         },
       },
       [")"] = {
         help = "next deep branch",
         messages = {
-          "NextVisitedDeepBranch",
+          { Repeat = { 1, { "NextVisitedDeepBranch" } } },
+        },
+      },
+      [","] = {
+        help = "repeat last action",
+        messages = {
+          "RepeatLastAction",
+        },
+      },
+      ["Q"] = {
+        help = "record macro",
+        messages = {
+          "PopMode",
+          { SwitchModeBuiltin = "record_macro" },
+        },
+      },
+      ["@"] = {
+        help = "play macro",
+        messages = {
+          "PopMode",
+          { SwitchModeBuiltin = "play_macro" },
         },
       },
       ["("] = {
         help = "prev deep branch",
         messages = {
-          "PreviousVisitedDeepBranch",
+          { Repeat = { 1, { "PreviousVisitedDeepBranch" } } },
         },
       },
       ["ctrl-r"] = {
@@ -1168,6 +1378,12 @@ xplr.config.modes.builtin.default = {
           "ClearSelection",
         },
       },
+      ["ctrl-t"] = {
+        help = "focus next panel",
+        messages = {
+          "CyclePanelFocus",
+        },
+      },
       ["ctrl-w"] = {
         help = "switch layout",
         messages = {
@@ -1184,7 +1400,7 @@ xplr.config.modes.builtin.default = {
       ["down"] = {
         help = "down",
         messages = {
-          "FocusNext",
+          { Repeat = { 1, { "FocusNext" } } },
         },
       },
       ["enter"] = {
@@ -1210,7 +1426,7 @@ xplr.config.modes.builtin.default = {
       ["left"] = {
         help = "back",
         messages = {
-          "Back",
+          { Repeat = { 1, { "Back" } } },
         },
       },
       ["q"] = {
@@ -1261,14 +1477,13 @@ xplr.config.modes.builtin.default = {
       ["space"] = {
         help = "toggle selection",
         messages = {
-          "ToggleSelection",
-          "FocusNext",
+          { Repeat = { 1, { "ToggleSelection", "FocusNext" } } },
         },
       },
       ["up"] = {
         help = "up",
         messages = {
-          "FocusPrevious",
+          { Repeat = { 1, { "FocusPrevious" } } },
         },
       },
       ["~"] = {
@@ -1284,37 +1499,43 @@ xplr.config.modes.builtin.default = {
       ["page-up"] = {
         help = "scroll up",
         messages = {
-          "ScrollUp",
+          { Repeat = { 1, { "ScrollUp" } } },
         },
       },
       ["page-down"] = {
         help = "scroll down",
         messages = {
-          "ScrollDown",
+          { Repeat = { 1, { "ScrollDown" } } },
         },
       },
       ["{"] = {
         help = "scroll up half",
         messages = {
-          "ScrollUpHalf",
+          { Repeat = { 1, { "ScrollUpHalf" } } },
         },
       },
       ["}"] = {
         help = "scroll down half",
         messages = {
-          "ScrollDownHalf",
+          { Repeat = { 1, { "ScrollDownHalf" } } },
+        },
+      },
+      ["ctrl-k"] = {
+        help = "command palette",
+        messages = {
+          "OpenCommandPalette",
         },
       },
       ["ctrl-n"] = {
         help = "next selection",
         messages = {
-          "FocusNextSelection",
+          { Repeat = { 1, { "FocusNextSelection" } } },
         },
       },
       ["ctrl-p"] = {
         help = "prev selection",
         messages = {
-          "FocusPreviousSelection",
+          { Repeat = { 1, { "FocusPreviousSelection" } } },
         },
       },
       ["m"] = {
@@ -1335,11 +1556,43 @@ xplr.config.modes.builtin.default = {
       },
     },
     on_number = {
-      help = "input",
+      help = "count",
       messages = {
-        "PopMode",
-        { SwitchModeBuiltin = "number" },
-        "BufferInputFromKey",
+        "UpdateCountFromKey",
+      },
+    },
+    on_mouse = {
+      left_click = {
+        messages = {
+          "FocusFromMouse",
+        },
+      },
+      double_click = {
+        messages = {
+          "FocusFromMouse",
+          "Enter",
+        },
+      },
+      right_click = {
+        messages = {
+          "ToggleSelectionFromMouse",
+        },
+      },
+      ctrl_left_click = {
+        messages = {
+          "ToggleSelectionFromMouse",
+        },
+      },
+      shift_left_click = {
+        messages = {
+          "FocusFromMouse",
+          "ToggleSelectionFromMouse",
+        },
+      },
+      shift_left_drag = {
+        messages = {
+          "SelectRangeFromMouse",
+        },
       },
     },
   },
@@ -2339,6 +2592,14 @@ xplr.config.modes.builtin.action = {
           { SwitchModeBuiltin = "create" },
         },
       },
+      ["d"] = {
+        help = "find duplicates",
+        messages = {
+          "PopMode",
+          { SwitchModeBuiltin = "duplicates" },
+          { FindDuplicates = "." },
+        },
+      },
       ["e"] = {
         help = "open in editor",
         messages = {
@@ -2362,6 +2623,22 @@ xplr.config.modes.builtin.action = {
           "PopMode",
         },
       },
+      [":"] = {
+        help = "command line",
+        messages = {
+          "PopMode",
+          { SwitchModeBuiltin = "command_line" },
+        },
+      },
+      ["L"] = {
+        help = "log viewer",
+        messages = {
+          "PopMode",
+          { SwitchModeBuiltin = "logs" },
+          { SwitchLayoutBuiltin = "logs" },
+          { FocusPanel = "Logs" },
+        },
+      },
       ["s"] = {
         help = "selection operations",
         messages = {
@@ -2381,12 +2658,7 @@ xplr.config.modes.builtin.action = {
         messages = {
           "PopMode",
           { SwitchModeBuiltin = "edit_permissions" },
-          {
-            BashExecSilently0 = [===[
-              PERM=$(stat -c '%a' -- "${XPLR_FOCUS_PATH:?}")
-              "$XPLR" -m 'SetInputBuffer: %q' "${PERM:?}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_reset" },
         },
       },
       ["v"] = {
@@ -2828,6 +3100,34 @@ xplr.config.modes.builtin.sort = {
           "ExplorePwdAsync",
         },
       },
+      ["u"] = {
+        help = "by user name",
+        messages = {
+          { AddNodeSorter = { sorter = "ByUserName", reverse = false } },
+          "ExplorePwdAsync",
+        },
+      },
+      ["U"] = {
+        help = "by user name reverse",
+        messages = {
+          { AddNodeSorter = { sorter = "ByUserName", reverse = true } },
+          "ExplorePwdAsync",
+        },
+      },
+      ["g"] = {
+        help = "by group name",
+        messages = {
+          { AddNodeSorter = { sorter = "ByGroupName", reverse = false } },
+          "ExplorePwdAsync",
+        },
+      },
+      ["G"] = {
+        help = "by group name reverse",
+        messages = {
+          { AddNodeSorter = { sorter = "ByGroupName", reverse = true } },
+          "ExplorePwdAsync",
+        },
+      },
     },
   },
 }
@@ -2934,6 +3234,326 @@ xplr.config.modes.builtin.vroot = {
   },
 }
 
+-- The builtin record macro mode.
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.record_macro = {
+  name = "record macro",
+  layout = "HelpMenu",
+  key_bindings = {
+    on_key = {
+      ["Q"] = {
+        help = "stop recording",
+        messages = {
+          "PopMode",
+          "StopMacroRecording",
+        },
+      },
+    },
+    on_alphabet = {
+      help = "record in register",
+      messages = {
+        "PopMode",
+        "StartMacroRecordingFromKey",
+      },
+    },
+  },
+}
+
+-- The builtin play macro mode.
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.play_macro = {
+  name = "play macro",
+  layout = "HelpMenu",
+  key_bindings = {
+    on_alphabet = {
+      help = "play from register",
+      messages = {
+        "PopMode",
+        "PlayMacroFromKey",
+      },
+    },
+  },
+}
+
+-- The builtin search panel mode.
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.search_panel = {
+  name = "search panel",
+  prompt = "/",
+  key_bindings = {
+    on_key = {
+      ["enter"] = {
+        help = "submit",
+        messages = {
+          "PopMode",
+        },
+      },
+    },
+    default = {
+      messages = {
+        "UpdateInputBufferFromKey",
+        "SearchPanelFromInput",
+      },
+    },
+  },
+}
+
+-- The builtin logs mode.
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.logs = {
+  name = "logs",
+  key_bindings = {
+    on_key = {
+      ["a"] = {
+        help = "all levels",
+        messages = {
+          "UnsetLogLevelFilter",
+        },
+      },
+      ["i"] = {
+        help = "info only",
+        messages = {
+          { SetLogLevelFilter = "INFO" },
+        },
+      },
+      ["s"] = {
+        help = "success only",
+        messages = {
+          { SetLogLevelFilter = "SUCCESS" },
+        },
+      },
+      ["w"] = {
+        help = "warning only",
+        messages = {
+          { SetLogLevelFilter = "WARNING" },
+        },
+      },
+      ["e"] = {
+        help = "error only",
+        messages = {
+          { SetLogLevelFilter = "ERROR" },
+        },
+      },
+      ["c"] = {
+        help = "copy focused log",
+        messages = {
+          "ActivatePanelRow",
+        },
+      },
+      ["C"] = {
+        help = "copy shown logs",
+        messages = {
+          "CopyLogs",
+        },
+      },
+      ["q"] = {
+        help = "quit log viewer",
+        messages = {
+          "UnfocusPanel",
+          "UnsetLogLevelFilter",
+          "PopMode",
+          { SwitchLayoutBuiltin = "default" },
+        },
+      },
+    },
+  },
+}
+
+xplr.config.modes.builtin.logs.key_bindings.on_key["esc"] =
+  xplr.config.modes.builtin.logs.key_bindings.on_key["q"]
+
+-- The builtin command palette mode.
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.command_palette = {
+  name = "command palette",
+  prompt = ">",
+  layout = {
+    Vertical = {
+      config = {
+        constraints = {
+          { Min = 1 },
+          { Length = 3 },
+        },
+      },
+      splits = {
+        "CommandPalette",
+        "InputAndLogs",
+      },
+    },
+  },
+  key_bindings = {
+    on_key = {
+      ["up"] = {
+        help = "up",
+        messages = {
+          "FocusPreviousCommandPaletteItem",
+        },
+      },
+      ["down"] = {
+        help = "down",
+        messages = {
+          "FocusNextCommandPaletteItem",
+        },
+      },
+      ["enter"] = {
+        help = "execute",
+        messages = {
+          "ExecuteCommandPaletteItem",
+        },
+      },
+      ["esc"] = {
+        help = "cancel",
+        messages = {
+          "CloseCommandPalette",
+        },
+      },
+    },
+    default = {
+      messages = {
+        "UpdateInputBufferFromKey",
+        "SearchCommandPaletteFromInput",
+      },
+    },
+  },
+}
+
+xplr.config.modes.builtin.command_palette.key_bindings.on_key["ctrl-n"] =
+  xplr.config.modes.builtin.command_palette.key_bindings.on_key["down"]
+xplr.config.modes.builtin.command_palette.key_bindings.on_key["ctrl-p"] =
+  xplr.config.modes.builtin.command_palette.key_bindings.on_key["up"]
+
+-- The builtin command line mode.
+--
+-- It's opened with `::`, i.e. `:` in the action mode, because `:` in the
+-- default mode opens the action mode, which existing configs and habits rely
+-- on, e.g. `:` followed by a number to go to an index. To open the command
+-- line with a single `:` instead, add this to your config:
+--
+-- ```lua
+-- xplr.config.modes.builtin.default.key_bindings.on_key[":"] = {
+--   help = "command line",
+--   messages = {
+--     "PopMode",
+--     { SwitchModeBuiltin = "command_line" },
+--   },
+-- }
+-- ```
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.command_line = {
+  name = "command line",
+  prompt = ":",
+  key_bindings = {
+    on_key = {
+      ["enter"] = {
+        help = "execute",
+        messages = {
+          "ExecuteCommandLineFromInput",
+          "PopMode",
+        },
+      },
+      ["tab"] = {
+        help = "complete",
+        messages = {
+          "CompleteCommandLine",
+        },
+      },
+      ["up"] = {
+        help = "previous command",
+        messages = {
+          "PreviousCommandLine",
+        },
+      },
+      ["down"] = {
+        help = "next command",
+        messages = {
+          "NextCommandLine",
+        },
+      },
+    },
+    default = {
+      messages = {
+        "UpdateInputBufferFromKey",
+      },
+    },
+  },
+}
+
+-- The builtin duplicates mode.
+--
+-- Type: [Mode](https://xplr.dev/en/mode)
+xplr.config.modes.builtin.duplicates = {
+  name = "duplicates",
+  key_bindings = {
+    on_key = {
+      ["a"] = {
+        help = "select all but one",
+        messages = {
+          "SelectDuplicates",
+        },
+      },
+      ["ctrl-u"] = {
+        help = "clear selection",
+        messages = {
+          "ClearSelection",
+        },
+      },
+      ["d"] = {
+        help = "delete",
+        messages = {
+          { SwitchModeBuiltin = "delete" },
+        },
+      },
+      ["down"] = {
+        help = "down",
+        messages = {
+          "FocusNext",
+        },
+      },
+      ["enter"] = {
+        help = "expand/collapse group",
+        messages = {
+          "ToggleDuplicateGroup",
+        },
+      },
+      ["esc"] = {
+        help = "done",
+        messages = {
+          "ClearDuplicates",
+          "PopMode",
+        },
+      },
+      ["space"] = {
+        help = "toggle selection",
+        messages = {
+          "ToggleSelection",
+          "FocusNext",
+        },
+      },
+      ["up"] = {
+        help = "up",
+        messages = {
+          "FocusPrevious",
+        },
+      },
+    },
+  },
+}
+
+xplr.config.modes.builtin.duplicates.key_bindings.on_key["j"] =
+  xplr.config.modes.builtin.duplicates.key_bindings.on_key["down"]
+xplr.config.modes.builtin.duplicates.key_bindings.on_key["k"] =
+  xplr.config.modes.builtin.duplicates.key_bindings.on_key["up"]
+xplr.config.modes.builtin.duplicates.key_bindings.on_key["v"] =
+  xplr.config.modes.builtin.duplicates.key_bindings.on_key["space"]
+xplr.config.modes.builtin.duplicates.key_bindings.on_key["tab"] =
+  xplr.config.modes.builtin.duplicates.key_bindings.on_key["enter"]
+
 -- The builtin edit permissions mode.
 --
 -- Type: [Mode](https://xplr.dev/en/mode)
@@ -2944,140 +3564,69 @@ xplr.config.modes.builtin.edit_permissions = {
       ["u"] = {
         help = "+user",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM="${XPLR_INPUT_BUFFER:-000}"
-              U="${PERM: -3:-2}"
-              G="${PERM: -2:-1}"
-              O="${PERM: -1}"
-
-              U="$(( (${U:-0} + 1) % 8 ))"
-              "$XPLR" -m 'SetInputBuffer: %q' "${U:-0}${G:-0}${O:-0}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_increment_user" },
         },
       },
       ["U"] = {
         help = "-user",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM="${XPLR_INPUT_BUFFER:-000}"
-              U="${PERM: -3:-2}"
-              G="${PERM: -2:-1}"
-              O="${PERM: -1}"
-
-              U="$(( ${U:-0}-1 < 0 ? 7 : ${U:-0}-1 ))"
-              "$XPLR" -m 'SetInputBuffer: %q' "${U:-0}${G:-0}${O:-0}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_decrement_user" },
         },
       },
       ["g"] = {
         help = "+group",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM="${XPLR_INPUT_BUFFER:-000}"
-              U="${PERM: -3:-2}"
-              G="${PERM: -2:-1}"
-              O="${PERM: -1}"
-
-              G="$(( (${G:-0} + 1) % 8 ))"
-              "$XPLR" -m 'SetInputBuffer: %q' "${U:-0}${G:-0}${O:-0}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_increment_group" },
         },
       },
       ["G"] = {
         help = "-group",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM="${XPLR_INPUT_BUFFER:-000}"
-              U="${PERM: -3:-2}"
-              G="${PERM: -2:-1}"
-              O="${PERM: -1}"
-
-              G="$(( ${G:-0}-1 < 0 ? 7 : ${G:-0}-1 ))"
-              "$XPLR" -m 'SetInputBuffer: %q' "${U:-0}${G:-0}${O:-0}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_decrement_group" },
         },
       },
       ["o"] = {
         help = "+other",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM="${XPLR_INPUT_BUFFER:-000}"
-              U="${PERM: -3:-2}"
-              G="${PERM: -2:-1}"
-              O="${PERM: -1}"
-
-              O="$(( (${O:-0} + 1) % 8 ))"
-              "$XPLR" -m 'SetInputBuffer: %q' "${U:-0}${G:-0}${O:-0}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_increment_other" },
         },
       },
       ["O"] = {
         help = "-other",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM="${XPLR_INPUT_BUFFER:-000}"
-              U="${PERM: -3:-2}"
-              G="${PERM: -2:-1}"
-              O="${PERM: -1}"
-
-              O="$(( ${O:-0}-1 < 0 ? 7 : ${O:-0}-1 ))"
-              "$XPLR" -m 'SetInputBuffer: %q' "${U:-0}${G:-0}${O:-0}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_decrement_other" },
         },
       },
       ["m"] = {
         help = "max",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              "$XPLR" -m 'SetInputBuffer: %q' "777"
-            ]===],
-          },
+          { SetInputBuffer = "777" },
         },
       },
       ["M"] = {
         help = "min",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              "$XPLR" -m 'SetInputBuffer: %q' "000"
-            ]===],
-          },
+          { SetInputBuffer = "000" },
         },
       },
       ["ctrl-r"] = {
         help = "reset",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              PERM=$(stat -c '%a' -- "${XPLR_FOCUS_PATH:?}")
-              "$XPLR" -m 'SetInputBuffer: %q' "${PERM:?}"
-            ]===],
-          },
+          { CallLuaSilently = "builtin.edit_permissions_reset" },
         },
       },
       ["enter"] = {
         help = "submit",
         messages = {
-          {
-            BashExecSilently0 = [===[
-              chmod "${XPLR_INPUT_BUFFER:?}" -- "${XPLR_FOCUS_PATH:?}"
-            ]===],
-          },
+          "SetPermissionsFromInput",
+          "PopMode",
+        },
+      },
+      ["r"] = {
+        help = "submit recursively",
+        messages = {
+          "SetPermissionsFromInputRecursive",
           "PopMode",
-          "ExplorePwdAsync",
         },
       },
     },
@@ -3118,6 +3667,35 @@ xplr.config.modes.builtin.edit_permissions = {
 -- ```
 xplr.config.modes.custom = {}
 
+-- ## Plugins -----------------------------------------------------------------
+--
+-- The plugins to load after the config files, in order. Each plugin is either
+-- a local directory, given by `path`, or a git repository, given by `git`, and
+-- optionally `rev` (a branch, tag or commit), that
+-- `xplr --plugins install|update|list` vendors into
+-- `$XDG_DATA_HOME/xplr/plugins/{name}`.
+--
+-- The plugin's `init.lua` is loaded, registered as the `name` module, and its
+-- `setup` function, if any, is called with the `setup` argument. If it
+-- exposes `xplr_version`, it's checked like the config's `version`. The
+-- plugins that fail to load are reported, without stopping the others.
+--
+-- Type: list of { name = string, path = string|nil, git = string|nil, rev = string|nil, setup = any }
+--
+-- Example:
+--
+-- ```lua
+-- xplr.config.plugins = {
+--   {
+--     name = "tri-pane",
+--     git = "https://github.com/sayanarijit/tri-pane.xplr",
+--     setup = { layout_key = "T" },
+--   },
+--   { name = "my-plugin", path = "~/dev/my-plugin.xplr" },
+-- }
+-- ```
+xplr.config.plugins = {}
+
 -- ## Function ----------------------------------------------------------------
 --
 -- While `xplr.config` defines all the static parts of the configuration,
@@ -3141,6 +3719,14 @@ xplr.fn.builtin.fmt_general_selection_item = function(n)
   return xplr.util.paint(shortened:gsub("\n", nl), style)
 end
 
+-- Focuses the node at the index given by the count prefix, else the last one
+xplr.fn.builtin.focus_last_or_count = function(app)
+  if app.count == nil then
+    return { "FocusLast" }
+  end
+  return { { FocusByIndex = app.count } }
+end
+
 -- Renders the first column in the table
 xplr.fn.builtin.fmt_general_table_row_cols_0 = function(m)
   local r = ""
@@ -3240,6 +3826,46 @@ xplr.fn.builtin.try_complete_path = function(m)
   }
 end
 
+-- Resets the permissions input buffer to the mode of the focused node
+xplr.fn.builtin.edit_permissions_reset = function(app)
+  if app.focused_node == nil then
+    return {}
+  end
+
+  local octal = xplr.util.permissions_octal(app.focused_node.permissions)
+  local perm = octal[2] .. octal[3] .. octal[4]
+  if octal[1] ~= 0 then
+    perm = octal[1] .. perm
+  end
+
+  return { { SetInputBuffer = perm } }
+end
+
+local function edit_permissions_step(index, step)
+  return function(app)
+    local perm = app.input_buffer or ""
+    local special = ""
+    if #perm > 3 then
+      special = perm:sub(1, #perm - 3)
+      perm = perm:sub(-3)
+    end
+    perm = string.rep("0", 3 - #perm) .. perm
+
+    local digit = ((tonumber(perm:sub(index, index)) or 0) + step) % 8
+    perm = perm:sub(1, index - 1) .. digit .. perm:sub(index + 1)
+
+    return { { SetInputBuffer = special .. perm } }
+  end
+end
+
+-- Increments or decrements a digit of the permissions input buffer
+xplr.fn.builtin.edit_permissions_increment_user = edit_permissions_step(1, 1)
+xplr.fn.builtin.edit_permissions_decrement_user = edit_permissions_step(1, -1)
+xplr.fn.builtin.edit_permissions_increment_group = edit_permissions_step(2, 1)
+xplr.fn.builtin.edit_permissions_decrement_group = edit_permissions_step(2, -1)
+xplr.fn.builtin.edit_permissions_increment_other = edit_permissions_step(3, 1)
+xplr.fn.builtin.edit_permissions_decrement_other = edit_permissions_step(3, -1)
+
 -- This is where the custom functions can be added.
 --
 -- There is currently no restriction on what kind of functions can be defined
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use tui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent as TuiMouseEvent,
+    MouseEventKind,
+};
 use tui_input::InputRequest;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -731,16 +734,15 @@ impl From<char> for Key {
     }
 }
 
+/// Parse the key from its string representation, i.e. the inverse of
+/// `Key::to_string`.
 impl From<&str> for Key {
     fn from(string: &str) -> Self {
-        if string.len() == 1 {
-            string
-                .chars()
-                .next()
-                .map(|c| c.into())
-                .unwrap_or(Key::NotSupported)
-        } else {
-            Key::NotSupported
+        let mut chars = string.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => c.into(),
+            _ if string == "space" => Key::Space,
+            _ => serde_yaml::from_str(string).unwrap_or(Key::NotSupported),
         }
     }
 }
@@ -759,6 +761,91 @@ impl PartialOrd for Key {
     }
 }
 
+/// The mouse actions that can be bound via `on_mouse`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseAction {
+    LeftClick,
+    DoubleClick,
+    RightClick,
+    MiddleClick,
+    CtrlLeftClick,
+    ShiftLeftClick,
+    AltLeftClick,
+    LeftDrag,
+    ShiftLeftDrag,
+    ScrollUp,
+    ScrollDown,
+}
+
+impl std::fmt::Display for MouseAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = serde_yaml::to_value(self)
+            .ok()
+            .and_then(|v| v.as_str().map(|v| v.to_string()))
+            .unwrap_or_default();
+
+        write!(f, "{action}")
+    }
+}
+
+/// A mouse event. The index of the node under the pointer is resolved by the
+/// UI, against the last rendered table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MouseEvent {
+    pub action: MouseAction,
+    pub column: u16,
+    pub row: u16,
+
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+impl MouseEvent {
+    /// Convert the terminal mouse event, returning `None` if it can't be bound.
+    /// Double clicks are detected by the caller.
+    pub fn from_event(evt: TuiMouseEvent) -> Option<Self> {
+        let action = match (evt.kind, evt.modifiers) {
+            (MouseEventKind::Down(MouseButton::Left), m)
+                if m.contains(KeyModifiers::CONTROL) =>
+            {
+                MouseAction::CtrlLeftClick
+            }
+            (MouseEventKind::Down(MouseButton::Left), m)
+                if m.contains(KeyModifiers::SHIFT) =>
+            {
+                MouseAction::ShiftLeftClick
+            }
+            (MouseEventKind::Down(MouseButton::Left), m)
+                if m.contains(KeyModifiers::ALT) =>
+            {
+                MouseAction::AltLeftClick
+            }
+            (MouseEventKind::Down(MouseButton::Left), _) => MouseAction::LeftClick,
+            (MouseEventKind::Down(MouseButton::Right), _) => MouseAction::RightClick,
+            (MouseEventKind::Down(MouseButton::Middle), _) => MouseAction::MiddleClick,
+            (MouseEventKind::Drag(MouseButton::Left), m)
+                if m.contains(KeyModifiers::SHIFT) =>
+            {
+                MouseAction::ShiftLeftDrag
+            }
+            (MouseEventKind::Drag(MouseButton::Left), _) => MouseAction::LeftDrag,
+            (MouseEventKind::ScrollUp, _) => MouseAction::ScrollUp,
+            (MouseEventKind::ScrollDown, _) => MouseAction::ScrollDown,
+            _ => return None,
+        };
+
+        Some(Self {
+            action,
+            column: evt.column,
+            row: evt.row,
+            index: None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InputOperation {
     SetCursor(usize),
@@ -797,3 +884,57 @@ impl Into<InputRequest> for InputOperation {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_from_str() {
+        for key in [
+            Key::A,
+            Key::ShiftA,
+            Key::Num0,
+            Key::Space,
+            Key::Enter,
+            Key::CtrlA,
+            Key::CtrlEnter,
+            Key::F1,
+            Key::Special(','),
+        ] {
+            assert_eq!(Key::from(key.to_string().as_str()), key);
+        }
+
+        assert_eq!(Key::from("foo"), Key::NotSupported);
+    }
+
+    #[test]
+    fn test_mouse_event_from_event() {
+        let evt = |kind, modifiers| TuiMouseEvent {
+            kind,
+            column: 1,
+            row: 2,
+            modifiers,
+        };
+
+        let left = MouseEventKind::Down(MouseButton::Left);
+        let action = |kind, modifiers| {
+            MouseEvent::from_event(evt(kind, modifiers)).map(|e| e.action)
+        };
+
+        assert_eq!(
+            action(left, KeyModifiers::NONE),
+            Some(MouseAction::LeftClick)
+        );
+        assert_eq!(
+            action(left, KeyModifiers::CONTROL),
+            Some(MouseAction::CtrlLeftClick)
+        );
+        assert_eq!(
+            action(MouseEventKind::Drag(MouseButton::Left), KeyModifiers::SHIFT),
+            Some(MouseAction::ShiftLeftDrag)
+        );
+        assert_eq!(action(MouseEventKind::Moved, KeyModifiers::NONE), None);
+        assert_eq!(MouseAction::ShiftLeftDrag.to_string(), "shift_left_drag");
+    }
+}
@@ -1,26 +1,38 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::from_over_into)]
 #![allow(clippy::unnecessary_wraps)]
+#![allow(clippy::unnecessary_sort_by)]
 
 pub mod app;
 pub mod cli;
+pub mod command_line;
+pub mod command_palette;
 pub mod compat;
 pub mod config;
 pub mod directory_buffer;
 pub mod dirs;
+pub mod duplicates;
 pub mod event_reader;
 pub mod explorer;
+pub mod hash;
 pub mod input;
+pub mod log_file;
 pub mod lua;
 pub mod msg;
 pub mod node;
 pub mod path;
 pub mod permissions;
 pub mod pipe;
+pub mod plugin;
 pub mod pwd_watcher;
 pub mod runner;
 pub mod search;
+pub mod socket;
+pub mod testing;
+pub mod timer;
 pub mod ui;
+pub mod users;
+pub mod xattr;
 pub mod yaml;
 
 #[cfg(test)]
@@ -0,0 +1,123 @@
+use crate::app::{ExternalMsg, MsgIn, Task};
+use crate::dirs;
+use anyhow::Result;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+
+/// The size after which the log file is moved to `xplr.log.1`, replacing the
+/// previous one.
+pub const MAX_SIZE: u64 = 1024 * 1024;
+
+pub fn path() -> Option<PathBuf> {
+    dirs::state_dir().map(|d| d.join("xplr").join("xplr.log"))
+}
+
+/// Append the line to the log file, rotating it first if it's too big.
+pub fn append(path: &Path, line: &str, max_size: u64) -> Result<()> {
+    if path.metadata().is_ok_and(|m| m.len() >= max_size) {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".1");
+        fs::rename(path, rotated)?;
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+pub struct Writer {
+    path: Option<PathBuf>,
+    failing: bool,
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self {
+            path: path(),
+            failing: false,
+        }
+    }
+}
+
+impl Writer {
+    /// Append the line to the log file. As the returned warning gets logged
+    /// too, a failure is only reported once, until the writes recover.
+    pub fn write(&mut self, line: &str) -> Option<String> {
+        let res = match self.path.as_ref() {
+            Some(path) => append(path, line, MAX_SIZE),
+            None => Err(anyhow::anyhow!("could not find the state directory")),
+        };
+
+        match res {
+            Ok(()) => {
+                self.failing = false;
+                None
+            }
+            Err(e) if !self.failing => {
+                self.failing = true;
+                Some(format!("could not write to the log file: {e}"))
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+/// Write the lines to the log file as they arrive, without blocking the UI.
+pub fn keep_writing(rx_log_file: Receiver<String>, tx_msg_in: Sender<Task>) {
+    thread::spawn(move || {
+        let mut writer = Writer::default();
+        for line in rx_log_file {
+            if let Some(warning) = writer.write(&line) {
+                let msg = MsgIn::External(ExternalMsg::LogWarning(warning));
+                tx_msg_in.send(Task::new(msg, None)).unwrap_or_default(); // Let's not panic if xplr closes.
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_append_rotates() {
+        let tmp = TempDir::new("append_rotates").unwrap();
+        let dir = tmp.path();
+        let path = dir.join("xplr.log");
+
+        append(&path, "one", 8).unwrap();
+        append(&path, "two", 8).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+
+        append(&path, "three", 8).unwrap();
+        let rotated = fs::read_to_string(dir.join("xplr.log.1")).unwrap();
+        let current = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(rotated, "one\ntwo\n");
+        assert_eq!(current, "three\n");
+    }
+
+    #[test]
+    fn test_writer_reports_once() {
+        let mut writer = Writer {
+            path: Some("/dev/null/xplr.log".into()),
+            failing: false,
+        };
+
+        assert!(writer.write("one").is_some());
+        assert_eq!(writer.write("two"), None);
+        assert_eq!(writer.write("three"), None);
+    }
+}
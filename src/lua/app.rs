@@ -0,0 +1,131 @@
+use crate::app::ExternalMsg;
+use anyhow::Result;
+use mlua::Error as LuaError;
+use mlua::Lua;
+use mlua::Table;
+use mlua::Value;
+
+const CONTEXT: &str = "xplr_app_context";
+
+/// The messages queued by the write methods during a function call.
+#[derive(Default)]
+struct Queue(Vec<ExternalMsg>);
+
+fn context(lua: &Lua) -> mlua::Result<Table> {
+    lua.named_registry_value::<Option<Table>>(CONTEXT)?
+        .ok_or_else(|| {
+            LuaError::RuntimeError(
+                "xplr.app is only available during the function calls".into(),
+            )
+        })
+}
+
+fn read(lua: &Lua, table: &Table, name: &str, field: &'static str) -> Result<()> {
+    let func = lua.create_function(move |lua, ()| context(lua)?.get::<Value>(field))?;
+    table.set(name, func)?;
+    Ok(())
+}
+
+fn write(
+    lua: &Lua,
+    table: &Table,
+    name: &str,
+    msg: fn(String) -> ExternalMsg,
+) -> Result<()> {
+    let func = lua.create_function(move |lua, path: String| {
+        context(lua)?;
+        if let Some(mut queue) = lua.app_data_mut::<Queue>() {
+            queue.0.push(msg(path));
+        }
+        Ok(())
+    })?;
+    table.set(name, func)?;
+    Ok(())
+}
+
+pub(crate) fn create_table(lua: &Lua) -> Result<Table> {
+    let app = lua.create_table()?;
+
+    read(lua, &app, "pwd", "pwd")?;
+    read(lua, &app, "focused", "focused_node")?;
+    read(lua, &app, "selection", "selection")?;
+    read(lua, &app, "mode", "mode")?;
+
+    write(lua, &app, "select", ExternalMsg::SelectPath)?;
+    write(lua, &app, "focus", ExternalMsg::FocusPath)?;
+    write(lua, &app, "cd", ExternalMsg::ChangeDirectory)?;
+
+    Ok(app)
+}
+
+/// Run the function call with `xplr.app` reading from the given context, and
+/// put the messages queued by its write methods before the returned ones.
+pub fn with_app(
+    lua: &Lua,
+    ctx: &Value,
+    call: impl FnOnce() -> Result<Option<Vec<ExternalMsg>>>,
+) -> Result<Option<Vec<ExternalMsg>>> {
+    lua.set_named_registry_value(CONTEXT, ctx)?;
+    lua.set_app_data(Queue::default());
+
+    let res = call();
+
+    lua.unset_named_registry_value(CONTEXT)?;
+    let queued = lua
+        .remove_app_data::<Queue>()
+        .map(|q| q.0)
+        .unwrap_or_default();
+
+    let msgs = res?;
+    if queued.is_empty() {
+        Ok(msgs)
+    } else {
+        Ok(Some(
+            queued.into_iter().chain(msgs.unwrap_or_default()).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LuaContextHeavy;
+    use crate::lua;
+
+    #[test]
+    fn test_app_handle() {
+        let lua = Lua::new();
+        lua::init(&lua).unwrap();
+        lua.load(
+            r#"
+            xplr.fn.custom.test = function(_)
+              xplr.app.select(xplr.app.pwd() .. "/foo")
+              xplr.app.cd("/")
+              return { { FocusPath = xplr.app.mode().name } }
+            end
+            "#,
+        )
+        .exec()
+        .unwrap();
+
+        let ctx = LuaContextHeavy {
+            pwd: "/tmp".into(),
+            ..Default::default()
+        };
+        let arg = lua::serialize(&lua, &ctx).unwrap();
+        let msgs = with_app(&lua, &arg, || lua::call(&lua, "custom.test", arg.clone()))
+            .unwrap();
+
+        assert_eq!(
+            msgs,
+            Some(vec![
+                ExternalMsg::SelectPath("/tmp/foo".into()),
+                ExternalMsg::ChangeDirectory("/".into()),
+                ExternalMsg::FocusPath("".into()),
+            ])
+        );
+
+        let res: mlua::Result<()> = lua.load("xplr.app.pwd()").exec();
+        assert!(res.is_err());
+    }
+}
@@ -11,7 +11,9 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::fs;
 
+pub mod app;
 pub mod util;
+pub mod worker;
 
 const DEFAULT_LUA_SCRIPT: &str = include_str!("../init.lua");
 const UPGRADE_GUIDE_LINK: &str = "https://xplr.dev/en/upgrade-guide";
@@ -68,6 +70,7 @@ pub fn init(lua: &Lua) -> Result<(Config, Option<Hooks>)> {
     let lua_xplr = lua.create_table()?;
     lua_xplr.set("config", serialize(lua, &config)?)?;
     lua_xplr.set("util", util)?;
+    lua_xplr.set("app", app::create_table(lua)?)?;
 
     let lua_xplr_fn = lua.create_table()?;
     let lua_xplr_fn_builtin = lua.create_table()?;
@@ -84,9 +87,7 @@ pub fn init(lua: &Lua) -> Result<(Config, Option<Hooks>)> {
         .call(())
         .and_then(|v| lua.from_value(v))?;
 
-    let lua_xplr: mlua::Table = globals.get("xplr")?;
-    let config: Config = lua.from_value(lua_xplr.get("config")?)?;
-    Ok((config, hooks))
+    Ok((current_config(lua)?, hooks))
 }
 
 /// Used to extend Lua globals
@@ -108,10 +109,44 @@ pub fn extend(lua: &Lua, path: &str) -> Result<(Config, Option<Hooks>)> {
 
     check_version(&version, path)?;
 
-    let lua_xplr: mlua::Table = globals.get("xplr")?;
+    Ok((current_config(lua)?, hooks))
+}
+
+/// Read `xplr.config`, as changed by the loaded scripts. The plugins' `setup`
+/// arguments are left out, as they may contain functions and are read from
+/// Lua when loading the plugins.
+pub fn current_config(lua: &Lua) -> Result<Config> {
+    let lua_xplr: mlua::Table = lua.globals().get("xplr")?;
+    let lua_config: mlua::Table = lua_xplr.get("config")?;
+
+    let copy = lua.create_table()?;
+    for pair in lua_config.pairs::<mlua::Value, mlua::Value>() {
+        let (key, value) = pair?;
+        copy.raw_set(key, value)?;
+    }
+
+    if let mlua::Value::Table(plugins) = lua_config.get("plugins")? {
+        let plugins_copy = lua.create_table()?;
+        for plugin in plugins.sequence_values::<mlua::Value>() {
+            let plugin = match plugin? {
+                mlua::Value::Table(plugin) => {
+                    let plugin_copy = lua.create_table()?;
+                    for pair in plugin.pairs::<mlua::Value, mlua::Value>() {
+                        let (key, value) = pair?;
+                        plugin_copy.raw_set(key, value)?;
+                    }
+                    plugin_copy.raw_set("setup", mlua::Nil)?;
+                    mlua::Value::Table(plugin_copy)
+                }
+                plugin => plugin,
+            };
+            plugins_copy.raw_push(plugin)?;
+        }
+        copy.raw_set("plugins", plugins_copy)?;
+    }
 
-    let config: Config = lua.from_value(lua_xplr.get("config")?)?;
-    Ok((config, hooks))
+    let config: Config = lua.from_value(mlua::Value::Table(copy))?;
+    Ok(config)
 }
 
 fn resolve_fn_recursive<'a>(
@@ -129,6 +164,12 @@ fn resolve_fn_recursive<'a>(
     }
 }
 
+/// Whether the Lua functions that change the file system are disabled. It's
+/// stored with `Lua::set_app_data`, out of the reach of the Lua code, e.g. by
+/// the `--read-only` flag.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOnly(pub bool);
+
 /// This function resolves paths like `builtin.func_foo`, `custom.func_bar` into lua functions.
 pub fn resolve_fn(globals: &mlua::Table, path: &str) -> Result<mlua::Function> {
     resolve_fn_recursive(globals, path.split('.'))
@@ -172,4 +213,36 @@ mod tests {
         // Next bugfix release is ERR (Change when we get to v1)
         assert!(check_version("1.1.1", "foo path").is_err());
     }
+
+    #[test]
+    fn test_edit_permissions_functions() {
+        let lua = Lua::new();
+        init(&lua).unwrap();
+
+        let step = |func: &str, input: &str| -> String {
+            let app = lua.create_table().unwrap();
+            app.set("input_buffer", input).unwrap();
+            let msgs: Vec<std::collections::HashMap<String, String>> =
+                call(&lua, func, mlua::Value::Table(app)).unwrap();
+            msgs[0]["SetInputBuffer"].clone()
+        };
+
+        assert_eq!(
+            step("builtin.edit_permissions_increment_user", "644"),
+            "744"
+        );
+        assert_eq!(
+            step("builtin.edit_permissions_increment_user", "744"),
+            "044"
+        );
+        assert_eq!(
+            step("builtin.edit_permissions_decrement_group", "600"),
+            "670"
+        );
+        assert_eq!(
+            step("builtin.edit_permissions_increment_other", "4755"),
+            "4756"
+        );
+        assert_eq!(step("builtin.edit_permissions_increment_other", "5"), "006");
+    }
 }
@@ -1,4 +1,5 @@
 use crate::app::VERSION;
+use crate::command_line::glob_to_regex;
 use crate::config::NodeTypesConfig;
 use crate::explorer;
 use crate::lua;
@@ -8,14 +9,17 @@ use crate::path;
 use crate::path::RelativityConfig;
 use crate::permissions::Octal;
 use crate::permissions::Permissions;
+use crate::timer;
 use crate::ui;
 use crate::ui::Layout;
 use crate::ui::Style;
 use crate::ui::WrapOptions;
+use crate::users;
 use anyhow::Result;
 use lazy_static::lazy_static;
 use lscolors::LsColors;
 use mlua::Error as LuaError;
+use mlua::FromLua;
 use mlua::Lua;
 use mlua::LuaSerdeExt;
 use mlua::Table;
@@ -26,7 +30,9 @@ use serde::{Deserialize, Serialize};
 use serde_json as json;
 use serde_yaml as yaml;
 use std::borrow::Cow;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 lazy_static! {
@@ -481,6 +487,224 @@ pub fn explore(util: Table, lua: &Lua) -> Result<Table> {
     Ok(util)
 }
 
+/// Read the file, or its first `max_bytes` bytes.
+///
+/// Like the other file system functions, it returns `nil` and an error on
+/// failure, e.g. `{ kind = "NotFound", message = "...", path = "/foo" }`,
+/// where `kind` is the name of the [io::ErrorKind][10]. The functions that
+/// change the file system fail with the `ReadOnly` kind in the read-only
+/// mode.
+///
+/// Type: function( path:string, { max_bytes = number|nil }|nil ) -> string|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.read_file("/etc/hostname")
+/// -- "localhost\n", nil
+///
+/// xplr.util.read_file("/foo/bar", { max_bytes = 1024 })
+/// -- nil, { kind = "NotFound", message = "No such file or directory (os error 2)", path = "/foo/bar" }
+/// ```
+pub fn read_file(util: Table, lua: &Lua) -> Result<Table> {
+    let func =
+        lua.create_function(|lua, (path, options): (String, Option<Table>)| {
+            let max_bytes: Option<u64> = option(&options, "max_bytes")?;
+            let res = fs::File::open(&path).and_then(|file| {
+                let mut buf = vec![];
+                match max_bytes {
+                    Some(n) => file.take(n).read_to_end(&mut buf),
+                    None => (&file).read_to_end(&mut buf),
+                }?;
+                Ok(buf)
+            });
+            match res {
+                Ok(buf) => Ok((Value::String(lua.create_string(buf)?), Value::Nil)),
+                Err(e) => fs_error(lua, &path, e),
+            }
+        })?;
+    util.set("read_file", func)?;
+    Ok(util)
+}
+
+/// Write the string to the file, creating it if needed, and replacing its
+/// content unless `append` is `true`.
+///
+/// Type: function( path:string, content:string, { append = boolean|nil }|nil ) -> true|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.write_file("/tmp/notes.txt", "foo\n", { append = true })
+/// -- true, nil
+/// ```
+pub fn write_file(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(
+        |lua, (path, content, options): (String, mlua::String, Option<Table>)| {
+            if is_read_only(lua) {
+                return read_only_error(lua, &path);
+            }
+            let append: bool = option(&options, "append")?.unwrap_or(false);
+            let res = fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&path)
+                .and_then(|mut file| file.write_all(&content.as_bytes()));
+            fs_result(lua, &path, res)
+        },
+    )?;
+    util.set("write_file", func)?;
+    Ok(util)
+}
+
+/// Create the directory, and its missing parents.
+///
+/// Type: function( path:string ) -> true|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.mkdir_p("/tmp/foo/bar")
+/// -- true, nil
+/// ```
+pub fn mkdir_p(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(|lua, path: String| {
+        if is_read_only(lua) {
+            return read_only_error(lua, &path);
+        }
+        fs_result(lua, &path, fs::create_dir_all(&path))
+    })?;
+    util.set("mkdir_p", func)?;
+    Ok(util)
+}
+
+/// Remove the file, the symlink, or the empty directory. Set `recursive` to
+/// `true` to also remove the non-empty directories. The symlinks are never
+/// followed.
+///
+/// Type: function( path:string, { recursive = boolean|nil }|nil ) -> true|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.remove("/tmp/foo", { recursive = true })
+/// -- true, nil
+/// ```
+pub fn remove(util: Table, lua: &Lua) -> Result<Table> {
+    let func =
+        lua.create_function(|lua, (path, options): (String, Option<Table>)| {
+            if is_read_only(lua) {
+                return read_only_error(lua, &path);
+            }
+            let recursive: bool = option(&options, "recursive")?.unwrap_or(false);
+            let res = fs::symlink_metadata(&path).and_then(|m| {
+                if !m.is_dir() {
+                    fs::remove_file(&path)
+                } else if recursive {
+                    fs::remove_dir_all(&path)
+                } else {
+                    fs::remove_dir(&path)
+                }
+            });
+            fs_result(lua, &path, res)
+        })?;
+    util.set("remove", func)?;
+    Ok(util)
+}
+
+/// Rename or move the file or directory, replacing the destination file if
+/// it exists.
+///
+/// Type: function( from:string, to:string ) -> true|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.rename("/tmp/foo.txt", "/tmp/bar.txt")
+/// -- true, nil
+/// ```
+pub fn rename(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(|lua, (from, to): (String, String)| {
+        if is_read_only(lua) {
+            return read_only_error(lua, &from);
+        }
+        fs_result(lua, &from, fs::rename(&from, &to))
+    })?;
+    util.set("rename", func)?;
+    Ok(util)
+}
+
+/// List the nodes in the directory and, recursively, in its sub-directories,
+/// sorted by path, without following the symlinks. `max_depth` limits how
+/// deep to go, `1` being the direct children. The nodes for which `filter`
+/// returns `false` or `nil` are left out, and so are their contents. The
+/// unreadable sub-directories are skipped.
+///
+/// Type: function( path:string, { max_depth = number|nil, filter = function( [Node][2] ) -> boolean|nil }|nil ) -> { [Node][2], ... }|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.walk("src", {
+///   max_depth = 2,
+///   filter = function(node)
+///     return node.is_dir or node.extension == "rs"
+///   end,
+/// })
+/// -- { { absolute_path = "/path/to/src/app.rs", ... }, ... }, nil
+/// ```
+pub fn walk(util: Table, lua: &Lua) -> Result<Table> {
+    let func =
+        lua.create_function(|lua, (path, options): (String, Option<Table>)| {
+            let max_depth: Option<usize> = option(&options, "max_depth")?;
+            let filter: Option<mlua::Function> = option(&options, "filter")?;
+
+            let root = PathBuf::from(&path).absolutize()?.to_path_buf();
+            let entries = match read_dir_sorted(&root) {
+                Ok(entries) => entries,
+                Err(e) => return fs_error(lua, &path, e),
+            };
+
+            let mut nodes = vec![];
+            walk_entries(lua, entries, 1, max_depth, filter.as_ref(), &mut nodes)?;
+            let nodes = lua::serialize(lua, &nodes).map_err(LuaError::custom)?;
+            Ok((nodes, Value::Nil))
+        })?;
+    util.set("walk", func)?;
+    Ok(util)
+}
+
+/// Find the paths matching the pattern, sorted. `*` matches any characters
+/// but `/`, `?` matches one character but `/`, and `**` matches any number
+/// of directories. The relative patterns are matched in the present working
+/// directory.
+///
+/// Type: function( pattern:string ) -> { string, ... }|nil, error|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.glob("src/**/*.rs")
+/// -- { "src/app.rs", "src/lua/mod.rs", ... }, nil
+///
+/// xplr.util.glob("/etc/*.conf")
+/// -- { "/etc/host.conf", "/etc/resolv.conf", ... }, nil
+/// ```
+pub fn glob(util: Table, lua: &Lua) -> Result<Table> {
+    let func =
+        lua.create_function(|lua, pattern: String| match glob_paths(&pattern) {
+            Ok(paths) => Ok((lua.to_value(&paths)?, Value::Nil)),
+            Err(e) => {
+                let err = error_table(lua, &pattern, "InvalidInput", &e.to_string())?;
+                Ok((Value::Nil, Value::Table(err)))
+            }
+        })?;
+    util.set("glob", func)?;
+    Ok(util)
+}
+
 /// Execute shell commands safely.
 ///
 /// Type: function( program:string, args:{ string, ... }|nil ) -> { stdout = string, stderr = string, returncode = number|nil }
@@ -850,6 +1074,112 @@ pub fn permissions_octal(util: Table, lua: &Lua) -> Result<Table> {
     Ok(util)
 }
 
+/// Get the name of the user with the given ID, or `nil` if there's no such
+/// user. The lookups are cached.
+///
+/// Type: function( number ) -> string|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.user_name(0)
+/// -- "root"
+///
+/// xplr.util.user_name(app.focused_node.uid)
+/// -- "alice"
+/// ```
+pub fn user_name(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(|_, uid: u32| Ok(users::user_name(uid)))?;
+    util.set("user_name", func)?;
+    Ok(util)
+}
+
+/// Get the name of the group with the given ID, or `nil` if there's no such
+/// group. The lookups are cached.
+///
+/// Type: function( number ) -> string|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.group_name(0)
+/// -- "root"
+///
+/// xplr.util.group_name(app.focused_node.gid)
+/// -- "staff"
+/// ```
+pub fn group_name(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(|_, gid: u32| Ok(users::group_name(gid)))?;
+    util.set("group_name", func)?;
+    Ok(util)
+}
+
+/// Call the function once, after the delay in milliseconds. Like with
+/// `CallLua`, it's called with the [Lua Context][9] and can return a list of
+/// messages to handle. Returns the timer id, to cancel it with
+/// `xplr.util.clear_timer` or the `CancelTimer` message, or `nil` when there
+/// are no timers, i.e. in the headless mode and in the `CallLuaAsync` worker.
+///
+/// Type: function( number, function( [Lua Context][9] ) -> { message, ... }|nil ) -> string|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.set_timeout(1000, function(app)
+///   return { { LogInfo = "still in " .. app.pwd } }
+/// end)
+/// -- "timer-0"
+/// ```
+pub fn set_timeout(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(|lua, (delay_ms, func): (u64, mlua::Function)| {
+        timer::schedule_lua(lua, delay_ms, false, func)
+    })?;
+    util.set("set_timeout", func)?;
+    Ok(util)
+}
+
+/// Like `xplr.util.set_timeout`, but call the function every given
+/// milliseconds, until the timer is cancelled. The interval must be greater
+/// than 0.
+///
+/// Type: function( number, function( [Lua Context][9] ) -> { message, ... }|nil ) -> string|nil
+///
+/// Example:
+///
+/// ```lua
+/// xplr.util.set_interval(5000, function(_)
+///   return { "ExplorePwdAsync" }
+/// end)
+/// -- "timer-1"
+/// ```
+pub fn set_interval(util: Table, lua: &Lua) -> Result<Table> {
+    let func =
+        lua.create_function(|lua, (interval_ms, func): (u64, mlua::Function)| {
+            timer::schedule_lua(lua, interval_ms, true, func)
+        })?;
+    util.set("set_interval", func)?;
+    Ok(util)
+}
+
+/// Cancel the timer with the given id. It does nothing if the timer has
+/// already fired or been cancelled, or if the id is `nil`.
+///
+/// Type: function( string|nil )
+///
+/// Example:
+///
+/// ```lua
+/// local id = xplr.util.set_interval(1000, function(_) end)
+/// xplr.util.clear_timer(id)
+/// ```
+pub fn clear_timer(util: Table, lua: &Lua) -> Result<Table> {
+    let func = lua.create_function(|lua, id: Option<String>| {
+        id.map(|id| timer::cancel_lua(lua, id)).unwrap_or(Ok(()))
+    })?;
+    util.set("clear_timer", func)?;
+    Ok(util)
+}
+
 ///
 /// [1]: https://xplr.dev/en/lua-function-calls#explorer-config
 /// [2]: https://xplr.dev/en/lua-function-calls#node
@@ -859,6 +1189,8 @@ pub fn permissions_octal(util: Table, lua: &Lua) -> Result<Table> {
 /// [6]: https://xplr.dev/en/node-type
 /// [7]: https://xplr.dev/en/node_types
 /// [8]: https://xplr.dev/en/column-renderer#permission
+/// [9]: https://xplr.dev/en/lua-function-calls#lua-context
+/// [10]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html
 ///
 pub(crate) fn create_table(lua: &Lua) -> Result<Table> {
     let mut util = lua.create_table()?;
@@ -880,6 +1212,13 @@ pub(crate) fn create_table(lua: &Lua) -> Result<Table> {
     util = relative_to(util, lua)?;
     util = shorten(util, lua)?;
     util = explore(util, lua)?;
+    util = read_file(util, lua)?;
+    util = write_file(util, lua)?;
+    util = mkdir_p(util, lua)?;
+    util = remove(util, lua)?;
+    util = rename(util, lua)?;
+    util = walk(util, lua)?;
+    util = glob(util, lua)?;
     util = shell_execute(util, lua)?;
     util = shell_quote(util, lua)?;
     util = shell_escape(util, lua)?;
@@ -894,6 +1233,230 @@ pub(crate) fn create_table(lua: &Lua) -> Result<Table> {
     util = layout_replace(util, lua)?;
     util = permissions_rwx(util, lua)?;
     util = permissions_octal(util, lua)?;
+    util = user_name(util, lua)?;
+    util = group_name(util, lua)?;
+    util = set_timeout(util, lua)?;
+    util = set_interval(util, lua)?;
+    util = clear_timer(util, lua)?;
 
     Ok(util)
 }
+
+fn option<T: FromLua>(options: &Option<Table>, key: &str) -> mlua::Result<Option<T>> {
+    match options {
+        Some(options) => options.get(key),
+        None => Ok(None),
+    }
+}
+
+fn is_read_only(lua: &Lua) -> bool {
+    lua.app_data_ref::<lua::ReadOnly>().is_some_and(|r| r.0)
+}
+
+fn error_table(lua: &Lua, path: &str, kind: &str, message: &str) -> mlua::Result<Table> {
+    let err = lua.create_table()?;
+    err.set("kind", kind)?;
+    err.set("message", message)?;
+    err.set("path", path)?;
+    Ok(err)
+}
+
+fn fs_error(lua: &Lua, path: &str, err: io::Error) -> mlua::Result<(Value, Value)> {
+    let kind = format!("{:?}", err.kind());
+    let err = error_table(lua, path, &kind, &err.to_string())?;
+    Ok((Value::Nil, Value::Table(err)))
+}
+
+fn fs_result(
+    lua: &Lua,
+    path: &str,
+    res: io::Result<()>,
+) -> mlua::Result<(Value, Value)> {
+    match res {
+        Ok(()) => Ok((Value::Boolean(true), Value::Nil)),
+        Err(e) => fs_error(lua, path, e),
+    }
+}
+
+fn read_only_error(lua: &Lua, path: &str) -> mlua::Result<(Value, Value)> {
+    let err = error_table(lua, path, "ReadOnly", "xplr is in read-only mode")?;
+    Ok((Value::Nil, Value::Table(err)))
+}
+
+/// An empty path is the present working directory, and its entries are
+/// listed relative to it.
+fn read_dir_sorted(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let is_pwd = dir.as_os_str().is_empty();
+    let mut paths = fs::read_dir(if is_pwd { Path::new(".") } else { dir })?
+        .filter_map(|e| e.ok())
+        .map(|e| {
+            if is_pwd {
+                e.file_name().into()
+            } else {
+                e.path()
+            }
+        })
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    Ok(paths)
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+fn walk_entries(
+    lua: &Lua,
+    paths: Vec<PathBuf>,
+    depth: usize,
+    max_depth: Option<usize>,
+    filter: Option<&mlua::Function>,
+    nodes: &mut Vec<Node>,
+) -> mlua::Result<()> {
+    for path in paths {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+
+        let node = Node::new(
+            parent.to_string_lossy().to_string(),
+            name.to_string_lossy().to_string(),
+        );
+
+        if let Some(filter) = filter {
+            let arg = lua::serialize(lua, &node).map_err(LuaError::custom)?;
+            if !filter.call::<bool>(arg)? {
+                continue;
+            }
+        }
+        nodes.push(node);
+
+        if is_real_dir(&path) && max_depth.is_none_or(|max| depth < max) {
+            if let Ok(children) = read_dir_sorted(&path) {
+                walk_entries(lua, children, depth + 1, max_depth, filter, nodes)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn glob_paths(pattern: &str) -> Result<Vec<String>> {
+    let (mut paths, pattern) = match pattern.strip_prefix('/') {
+        Some(rest) => (vec![PathBuf::from("/")], rest),
+        None => (vec![PathBuf::new()], pattern),
+    };
+
+    for part in pattern.split('/').filter(|p| !p.is_empty()) {
+        paths = if part == "**" {
+            let mut dirs = vec![];
+            let mut stack = paths;
+            while let Some(dir) = stack.pop() {
+                for child in read_dir_sorted(&dir).unwrap_or_default() {
+                    if is_real_dir(&child) {
+                        stack.push(child);
+                    }
+                }
+                dirs.push(dir);
+            }
+            dirs
+        } else if part.contains(['*', '?']) {
+            let regex = glob_to_regex(part)?;
+            paths
+                .iter()
+                .flat_map(|dir| read_dir_sorted(dir).unwrap_or_default())
+                .filter(|p| {
+                    p.file_name()
+                        .is_some_and(|n| regex.is_match(&n.to_string_lossy()))
+                })
+                .collect()
+        } else {
+            paths
+                .into_iter()
+                .map(|p| p.join(part))
+                .filter(|p| fs::symlink_metadata(p).is_ok())
+                .collect()
+        };
+    }
+
+    let mut paths = paths
+        .into_iter()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_fs_functions() {
+        let lua = Lua::new();
+        lua::init(&lua).unwrap();
+
+        let tmp = TempDir::new("lua_fs").unwrap();
+        let dir = tmp.path().join("fs");
+        lua.globals()
+            .set("dir", dir.to_string_lossy().to_string())
+            .unwrap();
+
+        lua.load(
+            r#"
+            local u = xplr.util
+            assert(u.mkdir_p(dir .. "/a/b"))
+            assert(u.write_file(dir .. "/a/x.txt", "foo"))
+            assert(u.write_file(dir .. "/a/x.txt", "bar", { append = true }))
+            assert(u.write_file(dir .. "/a/b/y.rs", ""))
+            assert(u.read_file(dir .. "/a/x.txt") == "foobar")
+            assert(u.read_file(dir .. "/a/x.txt", { max_bytes = 2 }) == "fo")
+
+            local nodes = u.walk(dir, {
+              filter = function(n) return n.is_dir or n.extension == "rs" end,
+            })
+            assert(#nodes == 3, #nodes)
+            assert(nodes[3].relative_path == "y.rs")
+            assert(#u.walk(dir, { max_depth = 2 }) == 3)
+
+            local paths, err = u.glob(dir .. "/**/*.rs")
+            assert(err == nil)
+            assert(#paths == 1 and paths[1] == dir .. "/a/b/y.rs", paths[1])
+            assert(#u.glob(dir .. "/a/*") == 2)
+
+            assert(u.rename(dir .. "/a/x.txt", dir .. "/a/z.txt"))
+            local ok, err = u.read_file(dir .. "/a/x.txt")
+            assert(ok == nil and err.kind == "NotFound", err.kind)
+
+            ok, err = u.remove(dir .. "/a")
+            assert(ok == nil and err.kind == "DirectoryNotEmpty", err.kind)
+            "#,
+        )
+        .exec()
+        .unwrap();
+
+        // The config can't turn off the read-only mode.
+        lua.set_app_data(lua::ReadOnly(true));
+        lua.load(
+            r#"
+            xplr.config.general.read_only = false
+            local ok, err = xplr.util.remove(dir, { recursive = true })
+            assert(ok == nil and err.kind == "ReadOnly", err.kind)
+            "#,
+        )
+        .exec()
+        .unwrap();
+
+        lua.set_app_data(lua::ReadOnly(false));
+        lua.load(
+            r#"
+            assert(xplr.util.remove(dir, { recursive = true }))
+            assert(not xplr.util.exists(dir))
+            "#,
+        )
+        .exec()
+        .unwrap();
+    }
+}
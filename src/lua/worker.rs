@@ -0,0 +1,181 @@
+use crate::app::{ExternalMsg, LuaContextHeavy, MsgIn, Task};
+use crate::lua;
+use crate::plugin;
+use anyhow::Result;
+use mlua::{Function, HookTriggers, Lua, Table, VmState};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Check the deadline every this many instructions.
+const HOOK_INTERVAL: u32 = 10_000;
+
+struct Job {
+    func: String,
+    ctx: LuaContextHeavy,
+    timeout: Option<Duration>,
+}
+
+/// A Lua state on its own thread, with the same config as the main one, that
+/// calls the functions one after another and enqueues the messages they
+/// return.
+pub struct Worker {
+    tx_job: Sender<Job>,
+}
+
+impl Worker {
+    pub fn start(
+        config_files: Vec<PathBuf>,
+        read_only: bool,
+        tx_msg_in: Sender<Task>,
+    ) -> Self {
+        let (tx_job, rx_job) = mpsc::channel::<Job>();
+
+        thread::spawn(move || {
+            // Why unsafe? See https://github.com/sayanarijit/xplr/issues/309
+            let lua = unsafe { Lua::unsafe_new() };
+            match load(&lua, &config_files, read_only) {
+                Ok(errs) => {
+                    for err in errs {
+                        let msg = format!("{err}, in the Lua worker");
+                        send(&tx_msg_in, ExternalMsg::LogError(msg));
+                    }
+                }
+                Err(e) => {
+                    let msg = format!("could not start the Lua worker: {e}");
+                    send(&tx_msg_in, ExternalMsg::LogError(msg));
+                    return;
+                }
+            }
+
+            for job in rx_job {
+                match call(&lua, &job) {
+                    Ok(msgs) => {
+                        for msg in msgs.unwrap_or_default() {
+                            send(&tx_msg_in, msg);
+                        }
+                    }
+                    Err(e) => {
+                        let msg = format!("could not call {0:?}: {e}", job.func);
+                        send(&tx_msg_in, ExternalMsg::LogError(msg));
+                    }
+                }
+            }
+        });
+
+        Self { tx_job }
+    }
+
+    /// Call the function on the worker, passing the context. A timeout of 0
+    /// means no timeout.
+    pub fn call(
+        &self,
+        func: String,
+        ctx: LuaContextHeavy,
+        timeout_ms: u64,
+    ) -> Result<()> {
+        let timeout = (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms));
+        self.tx_job.send(Job { func, ctx, timeout })?;
+        Ok(())
+    }
+}
+
+/// Load the config and the plugins, and return the plugins' errors.
+fn load(lua: &Lua, config_files: &[PathBuf], read_only: bool) -> Result<Vec<String>> {
+    let (mut config, _) = lua::init(lua)?;
+    for path in config_files {
+        (config, _) = lua::extend(lua, &path.to_string_lossy())?;
+    }
+    lua.set_app_data(lua::ReadOnly(read_only));
+    Ok(plugin::load_all(lua, &config.plugins))
+}
+
+fn call(lua: &Lua, job: &Job) -> Result<Option<Vec<ExternalMsg>>> {
+    // The hook isn't called from the JIT compiled code, so the JIT is turned
+    // off while there's a deadline.
+    let jit: Table = lua.globals().get("jit")?;
+    if job.timeout.is_some() {
+        jit.get::<Function>("off")?.call::<()>(())?;
+        jit.get::<Function>("flush")?.call::<()>(())?;
+    } else {
+        jit.get::<Function>("on")?.call::<()>(())?;
+    }
+
+    if let Some(timeout) = job.timeout {
+        let deadline = Instant::now() + timeout;
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INTERVAL),
+            move |_, _| {
+                if Instant::now() > deadline {
+                    Err(mlua::Error::RuntimeError(format!(
+                        "timed out after {timeout:?}"
+                    )))
+                } else {
+                    Ok(VmState::Continue)
+                }
+            },
+        )?;
+    }
+
+    let arg = lua::serialize(lua, &job.ctx)?;
+    let res = lua::app::with_app(lua, &arg, || lua::call(lua, &job.func, arg.clone()));
+    lua.remove_hook();
+    res
+}
+
+fn send(tx_msg_in: &Sender<Task>, msg: ExternalMsg) {
+    tx_msg_in
+        .send(Task::new(MsgIn::External(msg), None))
+        .unwrap_or_default(); // Let's not panic if xplr closes.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_worker() {
+        let dir = TempDir::new("lua_worker").unwrap();
+        let path = dir.path().join("init.lua");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+                version = "{}"
+                xplr.config.plugins = {{ {{ name = "missing", path = "/nonexistent" }} }}
+                xplr.fn.custom.pwd = function(ctx) return {{ {{ LogInfo = ctx.pwd }} }} end
+                xplr.fn.custom.forever = function(_) while true do end end
+                "#,
+                crate::app::VERSION
+            ),
+        )
+        .unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let worker = Worker::start(vec![path.clone()], false, tx);
+        let ctx = || LuaContextHeavy {
+            pwd: "/tmp".into(),
+            ..Default::default()
+        };
+
+        worker.call("custom.forever".into(), ctx(), 100).unwrap();
+        worker.call("custom.pwd".into(), ctx(), 0).unwrap();
+
+        let msg = |task: Task| match task.msg {
+            MsgIn::External(msg) => msg,
+            MsgIn::Internal(_) => panic!("unexpected internal message"),
+        };
+
+        match msg(rx.recv().unwrap()) {
+            ExternalMsg::LogError(e) => assert!(e.contains("\"missing\""), "{e}"),
+            m => panic!("unexpected message: {m:?}"),
+        }
+        match msg(rx.recv().unwrap()) {
+            ExternalMsg::LogError(e) => assert!(e.contains("timed out"), "{e}"),
+            m => panic!("unexpected message: {m:?}"),
+        }
+        assert_eq!(msg(rx.recv().unwrap()), ExternalMsg::LogInfo("/tmp".into()));
+    }
+}
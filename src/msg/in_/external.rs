@@ -1,8 +1,13 @@
+use crate::app::LogLevel;
 use crate::app::Node;
+use crate::hash::HashOptions;
 use crate::input::InputOperation;
+use crate::permissions::{Ownership, PermissionsOctal};
 use crate::search::PathItem;
 use crate::search::RankCriteria;
 use crate::search::SearchAlgorithm;
+use crate::users;
+use crate::xattr::Xattr;
 use indexmap::IndexSet;
 use rayon::iter::ParallelIterator;
 use regex::Regex;
@@ -434,6 +439,127 @@ pub enum ExternalMsg {
     /// - YAML: `ResetInputBuffer`
     ResetInputBuffer,
 
+    /// ### Count Prefix -------------------------------------------------------
+    ///
+    /// Append the digit read from the keyboard input to the count prefix, as in
+    /// `5j`. The count is passed to the action of the next key, and then
+    /// cleared. Leading zeros are ignored.
+    ///
+    /// The count is used by the `Repeat` message, and is available as
+    /// `XPLR_COUNT` in the environment of the executed commands.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"UpdateCountFromKey"`
+    /// - YAML: `UpdateCountFromKey`
+    UpdateCountFromKey,
+
+    /// Set the count prefix to the given number.
+    ///
+    /// Type: { SetCount = int }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetCount = 5 }`
+    /// - YAML: `SetCount: 5`
+    SetCount(usize),
+
+    /// Clear the count prefix.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ClearCount"`
+    /// - YAML: `ClearCount`
+    ClearCount,
+
+    /// Handle the given messages `n` times, multiplied by the count prefix if
+    /// any.
+    ///
+    /// Type: { Repeat = { int, { message, ... } } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ Repeat = { 1, { "FocusNext" } } }`
+    /// - YAML: `Repeat: [1, [FocusNext]]`
+    Repeat(usize, Vec<ExternalMsg>),
+
+    /// ### Repeating Actions --------------------------------------------------
+    ///
+    /// Repeat the last action executed from a key binding, like vim's `.`,
+    /// using the same input buffer and count prefix, unless a new count prefix
+    /// is given. Actions that only navigate, switch modes or layouts, or edit
+    /// the input buffer are not recorded.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"RepeatLastAction"`
+    /// - YAML: `RepeatLastAction`
+    RepeatLastAction,
+
+    /// ### Macro Operations ---------------------------------------------------
+    ///
+    /// Start recording the keys handled from now on into the given register.
+    /// The keys are recorded instead of the messages, so that the mode
+    /// transitions are replayed faithfully.
+    ///
+    /// Type: { StartMacroRecording = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ StartMacroRecording = "a" }`
+    /// - YAML: `StartMacroRecording: a`
+    StartMacroRecording(String),
+
+    /// Like `StartMacroRecording`, but the register is the key read from the
+    /// keyboard input.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"StartMacroRecordingFromKey"`
+    /// - YAML: `StartMacroRecordingFromKey`
+    StartMacroRecordingFromKey,
+
+    /// Stop recording the macro, and save the macros in `macros.json` in the
+    /// session directory, i.e. `$XPLR_SESSION_PATH`.
+    /// The keys used to stop the recording are not recorded.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"StopMacroRecording"`
+    /// - YAML: `StopMacroRecording`
+    StopMacroRecording,
+
+    /// Replay the keys recorded in the given register.
+    ///
+    /// Type: { PlayMacro = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ PlayMacro = "a" }`
+    /// - YAML: `PlayMacro: a`
+    PlayMacro(String),
+
+    /// Like `PlayMacro`, but the register is the key read from the keyboard
+    /// input.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"PlayMacroFromKey"`
+    /// - YAML: `PlayMacroFromKey`
+    PlayMacroFromKey,
+
+    /// Overwrite the keys in the given register. An empty list of keys removes
+    /// the macro. The recorded macros are available in the Lua context as
+    /// `macros`, for editing.
+    ///
+    /// Type: { SetMacro = { register = "string", keys = { "string", ... } } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetMacro = { register = "a", keys = { "j", "space" } } }`
+    /// - YAML: `SetMacro: { register: a, keys: [j, space] }`
+    SetMacro(Macro),
+
     /// ### Switching Mode -----------------------------------------------------
     ///
     /// Switch input [mode](https://xplr.dev/en/modes).
@@ -658,6 +784,19 @@ pub enum ExternalMsg {
     /// - YAML: `CallLuaSilently: custom.some_custom_function`
     CallLuaSilently(String),
 
+    /// Like `CallLuaSilently` but without blocking xplr. The function is
+    /// called on a separate Lua state, loaded with the same config, and the
+    /// messages it returns are handled once it finishes. It is interrupted
+    /// after `xplr.config.general.call_lua_async_timeout_ms`.
+    ///
+    /// Type: { CallLuaAsync = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ CallLuaAsync = "custom.some_custom_function" }`
+    /// - YAML: `CallLuaAsync: custom.some_custom_function`
+    CallLuaAsync(String),
+
     /// Execute Lua code without needing to define a function.
     ///
     /// If the `string` is a callable, xplr will try to call it with with the
@@ -1114,6 +1253,196 @@ pub enum ExternalMsg {
     /// - YAML: `CancelSearch`
     CancelSearch,
 
+    /// ### Duplicate Operations -----------------------------------------------
+    ///
+    /// Change directory to the given path, and recursively search it for
+    /// duplicate files in the background. Files are grouped by size, then by
+    /// partial and full content hash. Once done, the duplicate groups are
+    /// listed in place of the directory nodes, until `ClearDuplicates` is
+    /// called or the directory is changed.
+    ///
+    /// Each group is collapsed by default, i.e. only the first node of the
+    /// group is listed. Use `ToggleDuplicateGroup` to expand it.
+    ///
+    /// Type: { FindDuplicates = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ FindDuplicates = "/path/to/dir" }`
+    /// - YAML: `FindDuplicates: /path/to/dir`
+    FindDuplicates(String),
+
+    /// Expand or collapse the duplicate group of the focused node.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ToggleDuplicateGroup"`
+    /// - YAML: `ToggleDuplicateGroup`
+    ToggleDuplicateGroup,
+
+    /// Select all the duplicates, except the first node of each group. Useful
+    /// for deleting the duplicates.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SelectDuplicates"`
+    /// - YAML: `SelectDuplicates`
+    SelectDuplicates,
+
+    /// Stop listing the duplicate groups, and explore the present working
+    /// directory again.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ClearDuplicates"`
+    /// - YAML: `ClearDuplicates`
+    ClearDuplicates,
+
+    /// ### Hash Operations ----------------------------------------------------
+    ///
+    /// Compute the hash of the selected files, or the focused file if the
+    /// selection is empty, in the background. The hashes are cached until the
+    /// files are modified, and are available to the column renderer via
+    /// the `hashes` field.
+    ///
+    /// Supported algorithms: `sha256`, `sha1`, `md5` and `blake3`.
+    ///
+    /// Type: { Hash = { algo = "string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ Hash = { algo = "sha256" } }`
+    /// - YAML: `Hash: { algo: sha256 }`
+    Hash(HashOptions),
+
+    /// Copy the hash of the focused file to the clipboard, computing it first
+    /// if necessary. The clipboard is accessed via the OSC 52 escape sequence,
+    /// hence it requires a supporting terminal.
+    ///
+    /// Type: { CopyHash = { algo = "string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ CopyHash = { algo = "sha256" } }`
+    /// - YAML: `CopyHash: { algo: sha256 }`
+    CopyHash(HashOptions),
+
+    /// Read the given `SHA256SUMS`-style checksum file, and verify the listed
+    /// files in the background. Both the GNU (`<hash>  <path>`) and the BSD
+    /// (`SHA256 (<path>) = <hash>`) formats are supported. The algorithm is
+    /// guessed from the file name (e.g. `MD5SUMS`, `B3SUMS`), or from the
+    /// length of the hash.
+    ///
+    /// Each listed file is marked as `ok`, `mismatch` or `missing`, which is
+    /// available to the column renderer via the `checksum` field.
+    ///
+    /// Type: { VerifyChecksumFile = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ VerifyChecksumFile = "/path/to/SHA256SUMS" }`
+    /// - YAML: `VerifyChecksumFile: /path/to/SHA256SUMS`
+    VerifyChecksumFile(String),
+
+    /// ### Extended Attribute Operations --------------------------------------
+    ///
+    /// Set the given extended attribute on the selected paths, or the focused
+    /// path if the selection is empty. Symlinks are not followed.
+    ///
+    /// Type: { SetXattr = { name = "string", value = "string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetXattr = { name = "user.comment", value = "draft" } }`
+    /// - YAML: `SetXattr: { name: user.comment, value: draft }`
+    SetXattr(Xattr),
+
+    /// Like `SetXattr`, but the value is read from the input buffer.
+    ///
+    /// Type: { SetXattrFromInput = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetXattrFromInput = "user.comment" }`
+    /// - YAML: `SetXattrFromInput: user.comment`
+    SetXattrFromInput(String),
+
+    /// Remove the given extended attribute from the selected paths, or the
+    /// focused path if the selection is empty.
+    ///
+    /// Type: { RemoveXattr = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ RemoveXattr = "user.comment" }`
+    /// - YAML: `RemoveXattr: user.comment`
+    RemoveXattr(String),
+
+    /// ### Permission Operations ----------------------------------------------
+    ///
+    /// Change the mode of the selected paths, or the focused path if the
+    /// selection is empty, using the octal notation, like `chmod`.
+    ///
+    /// Type: { SetPermissions = { octal = "string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetPermissions = { octal = "755" } }`
+    /// - YAML: `SetPermissions: { octal: "755" }`
+    SetPermissions(PermissionsOctal),
+
+    /// Like `SetPermissions`, but also applies to everything under the selected
+    /// directories. Symlinks are not followed.
+    ///
+    /// Type: { SetPermissionsRecursive = { octal = "string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetPermissionsRecursive = { octal = "755" } }`
+    /// - YAML: `SetPermissionsRecursive: { octal: "755" }`
+    SetPermissionsRecursive(PermissionsOctal),
+
+    /// Like `SetPermissions`, but the octal notation is read from the input
+    /// buffer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SetPermissionsFromInput"`
+    /// - YAML: `SetPermissionsFromInput`
+    SetPermissionsFromInput,
+
+    /// Like `SetPermissionsRecursive`, but the octal notation is read from the
+    /// input buffer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SetPermissionsFromInputRecursive"`
+    /// - YAML: `SetPermissionsFromInputRecursive`
+    SetPermissionsFromInputRecursive,
+
+    /// Change the owner of the selected paths, or the focused path if the
+    /// selection is empty, like `chown`. The user and the group can be names or
+    /// numeric IDs. Omit one of them to keep it unchanged.
+    ///
+    /// Type: { ChangeOwner = { user = "nullable string", group = "nullable string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ ChangeOwner = { user = "alice", group = "staff" } }`
+    /// - YAML: `ChangeOwner: { user: alice, group: staff }`
+    ChangeOwner(Ownership),
+
+    /// Like `ChangeOwner`, but also applies to everything under the selected
+    /// directories. Symlinks are not followed.
+    ///
+    /// Type: { ChangeOwnerRecursive = { user = "nullable string", group = "nullable string" } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ ChangeOwnerRecursive = { group = "staff" } }`
+    /// - YAML: `ChangeOwnerRecursive: { group: staff }`
+    ChangeOwnerRecursive(Ownership),
+
     /// ### Mouse Operations ---------------------------------------------------
     ///
     /// Enable mouse
@@ -1140,6 +1469,264 @@ pub enum ExternalMsg {
     /// - YAML: `ToggleMouse`
     ToggleMouse,
 
+    /// Focus on the node under the mouse pointer. Mouse actions are bound via
+    /// the `on_mouse` field of the key bindings.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"FocusFromMouse"`
+    /// - YAML: `FocusFromMouse`
+    FocusFromMouse,
+
+    /// Toggle the selection of the node under the mouse pointer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ToggleSelectionFromMouse"`
+    /// - YAML: `ToggleSelectionFromMouse`
+    ToggleSelectionFromMouse,
+
+    /// Select the nodes from the focused node till the node under the mouse
+    /// pointer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SelectRangeFromMouse"`
+    /// - YAML: `SelectRangeFromMouse`
+    SelectRangeFromMouse,
+
+    /// ### Panel Operations ---------------------------------------------------
+    ///
+    /// Give the keyboard focus to the panel with the given name. It can be one
+    /// of `Table`, `HelpMenu`, `Selection`, `InputAndLogs`, or the name of a
+    /// `CustomList` or `CustomTable`. While the panel is focused, the
+    /// `panel_key_bindings` take precedence in the current mode.
+    ///
+    /// Type: { FocusPanel = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ FocusPanel = "menu" }`
+    /// - YAML: `FocusPanel: menu`
+    FocusPanel(String),
+
+    /// Take the keyboard focus away from the focused panel.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"UnfocusPanel"`
+    /// - YAML: `UnfocusPanel`
+    UnfocusPanel,
+
+    /// Move the keyboard focus to the next panel in the layout.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"CyclePanelFocus"`
+    /// - YAML: `CyclePanelFocus`
+    CyclePanelFocus,
+
+    /// Move the cursor of the focused panel to the next row.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"FocusNextPanelRow"`
+    /// - YAML: `FocusNextPanelRow`
+    FocusNextPanelRow,
+
+    /// Move the cursor of the focused panel to the previous row.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"FocusPreviousPanelRow"`
+    /// - YAML: `FocusPreviousPanelRow`
+    FocusPreviousPanelRow,
+
+    /// Send the messages of the focused panel row, if any. The same messages
+    /// are sent when the row is clicked.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ActivatePanelRow"`
+    /// - YAML: `ActivatePanelRow`
+    ActivatePanelRow,
+
+    /// Move the cursor of the focused panel to the first row, starting from
+    /// the cursor, that contains the given string, ignoring case.
+    ///
+    /// Type: { SearchPanel = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SearchPanel = "error" }`
+    /// - YAML: `SearchPanel: error`
+    SearchPanel(String),
+
+    /// Like `SearchPanel`, but the string is read from the input buffer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SearchPanelFromInput"`
+    /// - YAML: `SearchPanelFromInput`
+    SearchPanelFromInput,
+
+    /// Move the cursor of the focused panel to the next row that matches the
+    /// last search.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SearchPanelNext"`
+    /// - YAML: `SearchPanelNext`
+    SearchPanelNext,
+
+    /// Move the cursor of the focused panel to the previous row that matches
+    /// the last search.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SearchPanelPrevious"`
+    /// - YAML: `SearchPanelPrevious`
+    SearchPanelPrevious,
+
+    /// ### Command Palette ----------------------------------------------------
+    ///
+    /// Open the command palette, listing the key bindings with help text of
+    /// all the modes, in the builtin `command_palette` mode.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"OpenCommandPalette"`
+    /// - YAML: `OpenCommandPalette`
+    OpenCommandPalette,
+
+    /// Fuzzy search the command palette using the input buffer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"SearchCommandPaletteFromInput"`
+    /// - YAML: `SearchCommandPaletteFromInput`
+    SearchCommandPaletteFromInput,
+
+    /// Focus on the next item in the command palette.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"FocusNextCommandPaletteItem"`
+    /// - YAML: `FocusNextCommandPaletteItem`
+    FocusNextCommandPaletteItem,
+
+    /// Focus on the previous item in the command palette.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"FocusPreviousCommandPaletteItem"`
+    /// - YAML: `FocusPreviousCommandPaletteItem`
+    FocusPreviousCommandPaletteItem,
+
+    /// Close the command palette, leave its mode and send the messages of the
+    /// focused item.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ExecuteCommandPaletteItem"`
+    /// - YAML: `ExecuteCommandPaletteItem`
+    ExecuteCommandPaletteItem,
+
+    /// Close the command palette and leave its mode.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"CloseCommandPalette"`
+    /// - YAML: `CloseCommandPalette`
+    CloseCommandPalette,
+
+    /// ### Command Line -------------------------------------------------------
+    ///
+    /// Parse and run a vim-like command line. The supported commands are:
+    ///
+    /// - `cd [path]`: change directory, to `$HOME` by default.
+    /// - `sort [sorter [rev]]`: add a sorter, or clear the sorters.
+    /// - `filter [filter]`: add a filter, or clear the filters.
+    /// - `select glob...`: select the matching paths in the current directory.
+    /// - `!command`: run a shell command, where `%s` is the focused path.
+    /// - `q`, `quit`: quit.
+    ///
+    /// The sorters are `name`, `ext`, `size`, `created`, `modified`, `mime`,
+    /// `type`, `user`, `group`, or any [Node Sorter](https://xplr.dev/en/sorting#node-sorter-applicable)
+    /// name like `BySize`.
+    ///
+    /// The filters look like `ext=rs`, `name^=foo` or `!ipath*=bar`, where the
+    /// fields are `name`, `path` and `ext`, prefixed with `i` to ignore case,
+    /// and the operators are `=`, `!=`, `^=` (starts with), `$=` (ends with),
+    /// `*=` (contains) and `~=` (matches regex). `ext` only supports `=` and
+    /// `!=`. A leading `!` negates the filter.
+    ///
+    /// Type: { ExecuteCommandLine = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ ExecuteCommandLine = "sort size rev" }`
+    /// - YAML: `ExecuteCommandLine: filter ext=rs`
+    ExecuteCommandLine(String),
+
+    /// Like `ExecuteCommandLine`, but using the input buffer, and adding it to
+    /// the command line history.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"ExecuteCommandLineFromInput"`
+    /// - YAML: `ExecuteCommandLineFromInput`
+    ExecuteCommandLineFromInput,
+
+    /// Complete the command name, sorter name or path being typed in the input
+    /// buffer.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"CompleteCommandLine"`
+    /// - YAML: `CompleteCommandLine`
+    CompleteCommandLine,
+
+    /// Set the input buffer to the previous command line in the history.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"PreviousCommandLine"`
+    /// - YAML: `PreviousCommandLine`
+    PreviousCommandLine,
+
+    /// Set the input buffer to the next command line in the history.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"NextCommandLine"`
+    /// - YAML: `NextCommandLine`
+    NextCommandLine,
+
+    /// ### Timers -------------------------------------------------------------
+    ///
+    /// Enqueue the messages after the delay in milliseconds. Scheduling with
+    /// the `id` of a pending timer replaces it, which is useful to debounce.
+    ///
+    /// Type: { EnqueueAfter = { id = "string"|nil, delay_ms = int, messages = { message, ... } } }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ EnqueueAfter = { delay_ms = 500, messages = { "ExplorePwdAsync" } } }`
+    /// - YAML: `EnqueueAfter: { id: preview, delay_ms: 200, messages: [ CallLuaAsync: custom.preview ] }`
+    EnqueueAfter(DelayedMessages),
+
+    /// Cancel the pending timer with the given id, scheduled with
+    /// `EnqueueAfter`, `xplr.util.set_timeout` or `xplr.util.set_interval`.
+    ///
+    /// Type: { CancelTimer = "string" }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ CancelTimer = "preview" }`
+    /// - YAML: `CancelTimer: preview`
+    CancelTimer(String),
+
     /// ### Fifo Operations ----------------------------------------------------
     ///
     /// Start piping the focused path to the given fifo path
@@ -1212,6 +1799,45 @@ pub enum ExternalMsg {
     /// - YAML: `LogError: satellite crashed`
     LogError(String),
 
+    /// Show only the logs of the given level, e.g. in the `Logs` panel.
+    ///
+    /// Type: { SetLogLevelFilter = [Log Level](https://xplr.dev/en/layout#log-level) }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ SetLogLevelFilter = "ERROR" }`
+    /// - YAML: `SetLogLevelFilter: ERROR`
+    SetLogLevelFilter(LogLevel),
+
+    /// Show the logs of all levels.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"UnsetLogLevelFilter"`
+    /// - YAML: `UnsetLogLevelFilter`
+    UnsetLogLevelFilter,
+
+    /// Copy the log with the given ID to the clipboard. The ID is the index of
+    /// the log, counting from the first one of the session, including the ones
+    /// dropped due to `xplr.config.general.logs.max_entries`. It's the message
+    /// sent when activating a row of the `Logs` panel.
+    ///
+    /// Type: { CopyLog = int }
+    ///
+    /// Example:
+    ///
+    /// - Lua: `{ CopyLog = 0 }`
+    /// - YAML: `CopyLog: 0`
+    CopyLog(usize),
+
+    /// Copy the logs passing the log level filter to the clipboard.
+    ///
+    /// Example:
+    ///
+    /// - Lua: `"CopyLogs"`
+    /// - YAML: `CopyLogs`
+    CopyLogs,
+
     /// ### Debugging ----------------------------------------------------------
     ///
     /// Write the application state to a file, without quitting. Also helpful
@@ -1289,7 +1915,212 @@ pub enum ExternalMsg {
 }
 
 impl ExternalMsg {
+    /// Whether the message does more than navigating, switching modes or
+    /// layouts, editing the input buffer, logging or quitting. Only the actions
+    /// with such messages are recorded to be repeated via `RepeatLastAction`.
+    pub fn is_repeatable(&self) -> bool {
+        use ExternalMsg::*;
+
+        // Every message must be listed here, so that the new ones are
+        // classified explicitly.
+        match self {
+            Repeat(_, msgs) => msgs.iter().any(Self::is_repeatable),
+
+            ExplorePwd
+            | ExplorePwdAsync
+            | ExploreParentsAsync
+            | TryCompletePath
+            | ClearScreen
+            | Refresh
+            | FocusNext
+            | FocusNextSelection
+            | FocusNextByRelativeIndex(..)
+            | FocusNextByRelativeIndexFromInput
+            | FocusPrevious
+            | FocusPreviousSelection
+            | FocusPreviousByRelativeIndex(..)
+            | FocusPreviousByRelativeIndexFromInput
+            | FocusFirst
+            | FocusLast
+            | FocusPath(..)
+            | FocusPathFromInput
+            | FocusByIndex(..)
+            | FocusByIndexFromInput
+            | FocusByFileName(..)
+            | ScrollUp
+            | ScrollDown
+            | ScrollUpHalf
+            | ScrollDownHalf
+            | ChangeDirectory(..)
+            | Enter
+            | Back
+            | LastVisitedPath
+            | NextVisitedPath
+            | PreviousVisitedDeepBranch
+            | NextVisitedDeepBranch
+            | FollowSymlink
+            | SetInputPrompt(..)
+            | UpdateInputBuffer(..)
+            | UpdateInputBufferFromKey
+            | BufferInput(..)
+            | BufferInputFromKey
+            | SetInputBuffer(..)
+            | RemoveInputBufferLastCharacter
+            | RemoveInputBufferLastWord
+            | ResetInputBuffer
+            | UpdateCountFromKey
+            | SetCount(..)
+            | ClearCount
+            | RepeatLastAction
+            | StartMacroRecording(..)
+            | StartMacroRecordingFromKey
+            | StopMacroRecording
+            | SwitchMode(..)
+            | SwitchModeKeepingInputBuffer(..)
+            | SwitchModeBuiltin(..)
+            | SwitchModeBuiltinKeepingInputBuffer(..)
+            | SwitchModeCustom(..)
+            | SwitchModeCustomKeepingInputBuffer(..)
+            | PopMode
+            | PopModeKeepingInputBuffer
+            | SwitchLayout(..)
+            | SwitchLayoutBuiltin(..)
+            | SwitchLayoutCustom(..)
+            | FocusFromMouse
+            | FocusPanel(..)
+            | UnfocusPanel
+            | CyclePanelFocus
+            | FocusNextPanelRow
+            | FocusPreviousPanelRow
+            | SearchPanel(..)
+            | SearchPanelFromInput
+            | SearchPanelNext
+            | SearchPanelPrevious
+            | OpenCommandPalette
+            | SearchCommandPaletteFromInput
+            | FocusNextCommandPaletteItem
+            | FocusPreviousCommandPaletteItem
+            | ExecuteCommandPaletteItem
+            | CloseCommandPalette
+            | CompleteCommandLine
+            | PreviousCommandLine
+            | NextCommandLine
+            | LogInfo(..)
+            | LogSuccess(..)
+            | LogWarning(..)
+            | LogError(..)
+            | SetLogLevelFilter(..)
+            | UnsetLogLevelFilter
+            | CopyLog(..)
+            | CopyLogs
+            | Debug(..)
+            | Quit
+            | PrintPwdAndQuit
+            | PrintFocusPathAndQuit
+            | PrintSelectionAndQuit
+            | PrintResultAndQuit
+            | PrintAppStateAndQuit
+            | Terminate => false,
+
+            SetVroot(..)
+            | UnsetVroot
+            | ToggleVroot
+            | ResetVroot
+            | PlayMacro(..)
+            | PlayMacroFromKey
+            | SetMacro(..)
+            | Call(..)
+            | Call0(..)
+            | CallSilently(..)
+            | CallSilently0(..)
+            | BashExec(..)
+            | BashExec0(..)
+            | BashExecSilently(..)
+            | BashExecSilently0(..)
+            | CallLua(..)
+            | CallLuaSilently(..)
+            | CallLuaAsync(..)
+            | LuaEval(..)
+            | LuaEvalSilently(..)
+            | Select
+            | SelectAll
+            | SelectPath(..)
+            | UnSelect
+            | UnSelectAll
+            | UnSelectPath(..)
+            | ToggleSelection
+            | ToggleSelectAll
+            | ToggleSelectionByPath(..)
+            | ClearSelection
+            | AddNodeFilter(..)
+            | RemoveNodeFilter(..)
+            | ToggleNodeFilter(..)
+            | AddNodeFilterFromInput(..)
+            | RemoveNodeFilterFromInput(..)
+            | RemoveLastNodeFilter
+            | ResetNodeFilters
+            | ClearNodeFilters
+            | AddNodeSorter(..)
+            | RemoveNodeSorter(..)
+            | ReverseNodeSorter(..)
+            | ToggleNodeSorter(..)
+            | ReverseNodeSorters
+            | RemoveLastNodeSorter
+            | ResetNodeSorters
+            | ClearNodeSorters
+            | Search(..)
+            | SearchFromInput
+            | SearchFuzzy(..)
+            | SearchFuzzyFromInput
+            | SearchFuzzyUnordered(..)
+            | SearchFuzzyUnorderedFromInput
+            | SearchRegex(..)
+            | SearchRegexFromInput
+            | SearchRegexUnordered(..)
+            | SearchRegexUnorderedFromInput
+            | ToggleSearchAlgorithm
+            | EnableSearchOrder
+            | DisableSearchOrder
+            | ToggleSearchOrder
+            | AcceptSearch
+            | CancelSearch
+            | FindDuplicates(..)
+            | ToggleDuplicateGroup
+            | SelectDuplicates
+            | ClearDuplicates
+            | Hash(..)
+            | CopyHash(..)
+            | VerifyChecksumFile(..)
+            | SetXattr(..)
+            | SetXattrFromInput(..)
+            | RemoveXattr(..)
+            | SetPermissions(..)
+            | SetPermissionsRecursive(..)
+            | SetPermissionsFromInput
+            | SetPermissionsFromInputRecursive
+            | ChangeOwner(..)
+            | ChangeOwnerRecursive(..)
+            | EnableMouse
+            | DisableMouse
+            | ToggleMouse
+            | ToggleSelectionFromMouse
+            | SelectRangeFromMouse
+            | ActivatePanelRow
+            | ExecuteCommandLine(..)
+            | ExecuteCommandLineFromInput
+            | EnqueueAfter(..)
+            | CancelTimer(..)
+            | StartFifo(..)
+            | StopFifo
+            | ToggleFifo(..) => true,
+        }
+    }
+
     pub fn is_read_only(&self) -> bool {
+        if let Self::Repeat(_, msgs) = self {
+            return msgs.iter().all(Self::is_read_only);
+        }
+
         !matches!(
             self,
             Self::Call(_)
@@ -1302,12 +2133,31 @@ impl ExternalMsg {
                 | Self::BashExecSilently0(_)
                 | Self::CallLua(_)
                 | Self::CallLuaSilently(_)
+                | Self::CallLuaAsync(_)
                 | Self::LuaEval(_)
                 | Self::LuaEvalSilently(_)
+                | Self::SetXattr(_)
+                | Self::SetXattrFromInput(_)
+                | Self::RemoveXattr(_)
+                | Self::SetPermissions(_)
+                | Self::SetPermissionsRecursive(_)
+                | Self::SetPermissionsFromInput
+                | Self::SetPermissionsFromInputRecursive
+                | Self::ChangeOwner(_)
+                | Self::ChangeOwnerRecursive(_)
         )
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Macro {
+    pub register: String,
+
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub enum NodeSorter {
@@ -1323,6 +2173,8 @@ pub enum NodeSorter {
     BySize,
     ByCreated,
     ByLastModified,
+    ByUserName,
+    ByGroupName,
 
     ByCanonicalAbsolutePath,
     ByICanonicalAbsolutePath,
@@ -1391,6 +2243,14 @@ impl NodeSorterApplicable {
             NodeSorter::BySize => a.size.cmp(&b.size),
             NodeSorter::ByCreated => a.created.cmp(&b.created),
             NodeSorter::ByLastModified => a.last_modified.cmp(&b.last_modified),
+            NodeSorter::ByUserName => natord::compare(
+                &users::user_name(a.uid).unwrap_or_else(|| a.uid.to_string()),
+                &users::user_name(b.uid).unwrap_or_else(|| b.uid.to_string()),
+            ),
+            NodeSorter::ByGroupName => natord::compare(
+                &users::group_name(a.gid).unwrap_or_else(|| a.gid.to_string()),
+                &users::group_name(b.gid).unwrap_or_else(|| b.gid.to_string()),
+            ),
 
             NodeSorter::ByCanonicalAbsolutePath => natord::compare(
                 &a.canonical
@@ -1749,6 +2609,15 @@ struct NodeFilterApplicableDeserializer {
     pub input: String,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DelayedMessages {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub delay_ms: u64,
+    pub messages: Vec<ExternalMsg>,
+}
+
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, from = "NodeFilterApplicableDeserializer")]
 pub struct NodeFilterApplicable {
@@ -1903,6 +2772,9 @@ pub struct ExplorerConfig {
 
     #[serde(default)]
     pub searcher: Option<NodeSearcherApplicable>,
+
+    #[serde(default)]
+    pub load_extended_metadata: bool,
 }
 
 impl ExplorerConfig {
@@ -1937,3 +2809,28 @@ pub struct Command {
     #[serde(default)]
     pub args: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_repeatable() {
+        use ExternalMsg::*;
+
+        assert!(ToggleSelection.is_repeatable());
+        assert!(BashExec("true".into()).is_repeatable());
+        assert!(ExecuteCommandLineFromInput.is_repeatable());
+
+        assert!(!FocusNext.is_repeatable());
+        assert!(!SwitchModeBuiltin("action".into()).is_repeatable());
+        assert!(!SetInputBuffer("foo".into()).is_repeatable());
+        assert!(!StartMacroRecording("q".into()).is_repeatable());
+        assert!(!RepeatLastAction.is_repeatable());
+        assert!(!Quit.is_repeatable());
+
+        assert!(Repeat(1, vec![ToggleSelection, FocusNext]).is_repeatable());
+        assert!(!Repeat(1, vec![FocusNext]).is_repeatable());
+        assert!(!Repeat(1, vec![]).is_repeatable());
+    }
+}
@@ -1,5 +1,8 @@
 use crate::app::DirectoryBuffer;
-use crate::input::Key;
+use crate::duplicates::Duplicates;
+use crate::hash::NodeHash;
+use crate::input::{Key, MouseEvent};
+use crate::socket::Request;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -7,5 +10,12 @@ pub enum InternalMsg {
     AddLastFocus(String, Option<String>),
     SetDirectory(DirectoryBuffer),
     HandleKey(Key),
+    HandleMouse(MouseEvent),
+    KeySequenceTimeout(usize),
+    PlayNextMacroKey,
     RefreshSelection,
+    SetDuplicates(Duplicates),
+    SetNodeHashes(Vec<NodeHash>),
+    HandleSocketRequest(usize, Request),
+    CallLuaTimer(String),
 }
@@ -1,11 +1,19 @@
 use serde::{Deserialize, Serialize};
 
-use crate::app::{Command, Task};
+use crate::app::{Command, DelayedMessages, Task};
+use crate::hash::HashAlgorithm;
+use crate::socket::Event;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum MsgOut {
     ExplorePwdAsync,
     ExploreParentsAsync,
+    FindDuplicatesAsync(String),
+    HashAsync(Vec<String>, HashAlgorithm),
+    VerifyChecksumFileAsync(String),
+    CopyToClipboard(String),
+    WriteLogFile(String),
+    KeySequenceTimeoutAsync(usize, u64),
     Refresh,
     ClearScreen,
     Debug(String),
@@ -15,14 +23,26 @@ pub enum MsgOut {
     CallSilently0(Command),
     CallLua(String),
     CallLuaSilently(String),
+    CallLuaAsync(String),
+    CallLuaTimer(String),
+    EnqueueAfter(DelayedMessages),
+    CancelTimer(String),
     LuaEval(String),
     LuaEvalSilently(String),
     EnableMouse,
     DisableMouse,
     ToggleMouse,
+    CyclePanelFocus,
+    MovePanelCursor(isize),
+    ActivatePanelRow,
+    SearchPanel(String),
+    SearchPanelNext,
+    SearchPanelPrevious,
     StartFifo(String),
     StopFifo,
     ToggleFifo(String),
+    SocketReply(usize, String),
+    PublishEvent(Event),
     ScrollUp,
     ScrollDown,
     ScrollUpHalf,
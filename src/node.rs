@@ -1,4 +1,5 @@
 use crate::permissions::Permissions;
+use crate::xattr::ExtendedMetadata;
 use humansize::{format_size, DECIMAL};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -129,6 +130,9 @@ pub struct Node {
 
     pub canonical: Option<ResolvedNode>,
     pub symlink: Option<ResolvedNode>,
+
+    /// Only loaded when `general.load_extended_metadata` is enabled.
+    pub extended_metadata: Option<ExtendedMetadata>,
 }
 
 impl Node {
@@ -229,8 +233,15 @@ impl Node {
             } else {
                 None
             },
+            extended_metadata: None,
         }
     }
+
+    pub fn with_extended_metadata(mut self) -> Self {
+        self.extended_metadata =
+            Some(ExtendedMetadata::load(Path::new(&self.absolute_path)));
+        self
+    }
 }
 
 impl Ord for Node {
@@ -1,6 +1,11 @@
 // Stolen from https://github.com/Peltoche/lsd/blob/master/src/meta/permissions.rs
 
 use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 use std::{fmt::Display, fs::Metadata};
 
 pub type RWX = (char, char, char, char, char, char, char, char, char);
@@ -45,7 +50,52 @@ pub struct Permissions {
     pub setuid: bool,
 }
 
-impl Permissions {}
+impl Permissions {
+    /// Parse octal notation, for e.g. `755` or `4755`.
+    pub fn from_octal(octal: &str) -> Option<Self> {
+        let octal = octal.trim();
+        if octal.is_empty() || octal.len() > 4 {
+            return None;
+        }
+
+        let mut digits = [0_u8; 4];
+        for (i, c) in octal.chars().rev().enumerate() {
+            digits[3 - i] = c.to_digit(8)? as u8;
+        }
+
+        Some((digits[0], digits[1], digits[2], digits[3]).into())
+    }
+
+    /// The mode bits as accepted by `chmod`.
+    pub fn mode(&self) -> u32 {
+        let (special, user, group, other): Octal = (*self).into();
+        (special as u32) << 9 | (user as u32) << 6 | (group as u32) << 3 | other as u32
+    }
+}
+
+impl From<Octal> for Permissions {
+    fn from((special, user, group, other): Octal) -> Self {
+        let has_bit = |digit: u8, bit: u8| digit & bit == bit;
+
+        Self {
+            user_read: has_bit(user, 4),
+            user_write: has_bit(user, 2),
+            user_execute: has_bit(user, 1),
+
+            group_read: has_bit(group, 4),
+            group_write: has_bit(group, 2),
+            group_execute: has_bit(group, 1),
+
+            other_read: has_bit(other, 4),
+            other_write: has_bit(other, 2),
+            other_execute: has_bit(other, 1),
+
+            setuid: has_bit(special, 4),
+            setgid: has_bit(special, 2),
+            sticky: has_bit(special, 1),
+        }
+    }
+}
 
 impl From<&Metadata> for Permissions {
     #[cfg(unix)]
@@ -166,3 +216,199 @@ mod modes {
     pub const SETGID: Mode = libc::S_ISGID as Mode;
     pub const SETUID: Mode = libc::S_ISUID as Mode;
 }
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionsOctal {
+    pub octal: String,
+}
+
+/// The new owner of a path. The user and the group can be names or numeric IDs.
+/// Leave one of them empty to keep it unchanged.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ownership {
+    #[serde(default)]
+    pub user: Option<String>,
+
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+impl Ownership {
+    /// Resolve the user and the group into numeric IDs.
+    pub fn resolve(&self) -> io::Result<(Option<u32>, Option<u32>)> {
+        let not_found = |kind: &str, name: &str| {
+            io::Error::new(io::ErrorKind::NotFound, format!("no such {kind}: {name}"))
+        };
+
+        let uid = match self.user.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(user) => match user.parse() {
+                Ok(uid) => Some(uid),
+                Err(_) => {
+                    let name = CString::new(user).map_err(io::Error::other)?;
+                    let pw = unsafe { libc::getpwnam(name.as_ptr()) };
+                    if pw.is_null() {
+                        return Err(not_found("user", user));
+                    }
+                    Some(unsafe { (*pw).pw_uid })
+                }
+            },
+        };
+
+        let gid = match self.group.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(group) => match group.parse() {
+                Ok(gid) => Some(gid),
+                Err(_) => {
+                    let name = CString::new(group).map_err(io::Error::other)?;
+                    let gr = unsafe { libc::getgrnam(name.as_ptr()) };
+                    if gr.is_null() {
+                        return Err(not_found("group", group));
+                    }
+                    Some(unsafe { (*gr).gr_gid })
+                }
+            },
+        };
+
+        Ok((uid, gid))
+    }
+}
+
+/// The outcome of applying a change to one or more paths.
+#[derive(Debug, Default)]
+pub struct ChangeReport {
+    pub changed: usize,
+    pub errors: Vec<(String, io::Error)>,
+}
+
+impl ChangeReport {
+    fn record(&mut self, path: &Path, res: io::Result<()>) {
+        match res {
+            Ok(()) => self.changed += 1,
+            Err(e) => self.errors.push((path.to_string_lossy().to_string(), e)),
+        }
+    }
+}
+
+/// Apply `f` to the path and, if `recursive`, to everything under it. Symlinks
+/// found while recursing are passed to `f`, but not followed.
+fn apply<F>(path: &Path, recursive: bool, f: &F, report: &mut ChangeReport)
+where
+    F: Fn(&Path, bool) -> io::Result<()>,
+{
+    report.record(path, f(path, true));
+
+    let is_dir = path.symlink_metadata().map(|m| m.is_dir()).unwrap_or(false);
+    if recursive && is_dir {
+        walk(path, f, report);
+    }
+}
+
+fn walk<F>(dir: &Path, f: &F, report: &mut ChangeReport)
+where
+    F: Fn(&Path, bool) -> io::Result<()>,
+{
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.record(dir, Err(e));
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        report.record(&path, f(&path, false));
+        if is_dir {
+            walk(&path, f, report);
+        }
+    }
+}
+
+/// Change the mode of the path, like `chmod`.
+pub fn set_permissions(
+    path: &Path,
+    permissions: Permissions,
+    recursive: bool,
+) -> ChangeReport {
+    let mut report = ChangeReport::default();
+    let mode = permissions.mode();
+    apply(
+        path,
+        recursive,
+        &|p: &Path, follow| {
+            // The mode of a symlink itself is meaningless.
+            if !follow && p.symlink_metadata()?.file_type().is_symlink() {
+                return Ok(());
+            }
+            fs::set_permissions(p, fs::Permissions::from_mode(mode))
+        },
+        &mut report,
+    );
+    report
+}
+
+/// Change the owner of the path, like `chown`.
+pub fn change_owner(
+    path: &Path,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    recursive: bool,
+) -> ChangeReport {
+    let mut report = ChangeReport::default();
+    apply(
+        path,
+        recursive,
+        &|p: &Path, follow| {
+            if follow {
+                std::os::unix::fs::chown(p, uid, gid)
+            } else {
+                std::os::unix::fs::lchown(p, uid, gid)
+            }
+        },
+        &mut report,
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_from_octal() {
+        let perms = Permissions::from_octal("754").unwrap();
+        assert_eq!(perms.to_string(), "rwxr-xr--");
+        assert_eq!(perms.mode(), 0o754);
+
+        let perms = Permissions::from_octal("4755").unwrap();
+        assert_eq!(perms.to_string(), "rwsr-xr-x");
+        assert_eq!(perms.mode(), 0o4755);
+
+        assert_eq!(Permissions::from_octal("7").unwrap().mode(), 0o7);
+        assert_eq!(Permissions::from_octal("789"), None);
+        assert_eq!(Permissions::from_octal("17555"), None);
+        assert_eq!(Permissions::from_octal(""), None);
+    }
+
+    #[test]
+    fn test_set_permissions_recursive() {
+        let tmp = TempDir::new("permissions").unwrap();
+        let dir = tmp.path();
+        let sub = dir.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join("file"), "").unwrap();
+
+        let perms = Permissions::from_octal("750").unwrap();
+        let report = set_permissions(dir, perms, true);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.changed, 3);
+
+        let mode = fs::metadata(sub.join("file")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o750);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::app;
+use crate::command_line::expand_home;
+use crate::config::PluginConfig;
+use crate::dirs;
+use crate::lua;
+use anyhow::{bail, Context, Result};
+use mlua::{Function, Lua, Table, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Where the git plugins are installed.
+pub fn plugins_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("xplr").join("plugins"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginCommand {
+    Install,
+    Update,
+    List,
+}
+
+impl std::str::FromStr for PluginCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "install" => Ok(Self::Install),
+            "update" => Ok(Self::Update),
+            "list" => Ok(Self::List),
+            _ => bail!("invalid plugin command: {s:?}"),
+        }
+    }
+}
+
+impl PluginConfig {
+    /// The directory containing the plugin's `init.lua`. The relative local
+    /// paths are relative to the xplr config directory.
+    pub fn dir(&self) -> Result<PathBuf> {
+        match (&self.path, &self.git) {
+            (Some(path), None) => {
+                let path = PathBuf::from(expand_home(path));
+                if path.is_absolute() {
+                    Ok(path)
+                } else {
+                    dirs::config_dir()
+                        .map(|d| d.join("xplr").join(path))
+                        .context("could not find the config directory")
+                }
+            }
+            (None, Some(_))
+                if self.name.is_empty() || self.name.contains(['/', '.']) =>
+            {
+                bail!("the name of a git plugin can't be empty or contain `/` or `.`")
+            }
+            (None, Some(_)) => plugins_dir()
+                .map(|d| d.join(&self.name))
+                .context("could not find the data directory"),
+            _ => bail!("exactly one of `path` and `git` must be set"),
+        }
+    }
+}
+
+/// Load the plugins, in the given order, and call their `setup` functions.
+/// Returns an error per plugin that failed to load.
+pub fn load_all(lua: &Lua, plugins: &[PluginConfig]) -> Vec<String> {
+    if plugins.is_empty() {
+        return vec![];
+    }
+
+    if let Err(e) = add_package_path(lua) {
+        return vec![format!("could not set the plugins path: {e}")];
+    }
+
+    plugins
+        .iter()
+        .enumerate()
+        .filter_map(|(i, plugin)| {
+            load(lua, i, plugin)
+                .err()
+                .map(|e| format!("could not load plugin {:?}: {e}", plugin.name))
+        })
+        .collect()
+}
+
+/// Let the git plugins `require` their own modules.
+fn add_package_path(lua: &Lua) -> Result<()> {
+    let Some(dir) = plugins_dir() else {
+        return Ok(());
+    };
+    let dir = dir.to_string_lossy();
+
+    let package: Table = lua.globals().get("package")?;
+    let path: String = package.get("path")?;
+    package.set("path", format!("{dir}/?/init.lua;{dir}/?.lua;{path}"))?;
+    Ok(())
+}
+
+fn load(lua: &Lua, index: usize, plugin: &PluginConfig) -> Result<()> {
+    let path = plugin.dir()?.join("init.lua");
+    if !path.exists() {
+        if plugin.git.is_some() {
+            bail!("not installed, run `xplr --plugins install`")
+        } else {
+            bail!("{:?} doesn't exist", path.to_string_lossy())
+        }
+    }
+
+    let name = path.to_string_lossy().to_string();
+    let module: Value = lua.load(fs::read(&path)?).set_name(&name).call(())?;
+
+    let Value::Table(module) = module else {
+        return Ok(());
+    };
+
+    if let Some(version) = module.get::<Option<String>>("xplr_version")? {
+        lua::check_version(&version, &name)?;
+    }
+
+    let loaded: Table = lua.globals().get::<Table>("package")?.get("loaded")?;
+    loaded.set(plugin.name.as_str(), &module)?;
+
+    if let Some(setup) = module.get::<Option<Function>>("setup")? {
+        // Read from Lua, as it may contain functions.
+        let args = lua
+            .globals()
+            .get::<Table>("xplr")?
+            .get::<Table>("config")?
+            .get::<Table>("plugins")?
+            .get::<Table>(index + 1)?
+            .get::<Value>("setup")?;
+        setup.call::<()>(args)?;
+    }
+
+    Ok(())
+}
+
+fn git(args: &[&str]) -> Result<()> {
+    let status = Command::new("git").args(args).status()?;
+    if !status.success() {
+        bail!("`git {}` failed with {status}", args.join(" "))
+    }
+    Ok(())
+}
+
+/// Check out the revision, if given, as fetched from the remote.
+fn checkout(dir: &str, rev: Option<&str>) -> Result<()> {
+    if let Some(rev) = rev {
+        git(&["-C", dir, "fetch", "origin", "--", rev])?;
+        git(&["-C", dir, "checkout", "--detach", "FETCH_HEAD"])?;
+    }
+    Ok(())
+}
+
+fn install(plugin: &PluginConfig) -> Result<&'static str> {
+    let Some(url) = &plugin.git else {
+        return Ok("local");
+    };
+
+    let dir = plugin.dir()?;
+    if dir.exists() {
+        return Ok("already installed");
+    }
+
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let dir = dir.to_string_lossy();
+    git(&["clone", "--", url, &dir])?;
+    checkout(&dir, plugin.rev.as_deref())?;
+    Ok("installed")
+}
+
+fn update(plugin: &PluginConfig) -> Result<&'static str> {
+    if plugin.git.is_none() {
+        return Ok("local");
+    };
+
+    let dir = plugin.dir()?;
+    if !dir.exists() {
+        return install(plugin);
+    }
+
+    let dir = dir.to_string_lossy();
+    if plugin.rev.is_some() {
+        checkout(&dir, plugin.rev.as_deref())?;
+    } else {
+        git(&["-C", &dir, "pull", "--ff-only"])?;
+    }
+    Ok("updated")
+}
+
+fn status(plugin: &PluginConfig) -> Result<&'static str> {
+    let installed = plugin.dir()?.join("init.lua").exists();
+    Ok(match (&plugin.git, installed) {
+        (Some(_), true) => "installed",
+        (Some(_), false) => "not installed",
+        (None, true) => "local",
+        (None, false) => "missing",
+    })
+}
+
+/// Run the `--plugins` command on the plugins listed in the config, printing
+/// a line per plugin. Fails if any of them failed.
+pub fn manage(
+    command: PluginCommand,
+    config_file: Option<PathBuf>,
+    extra_config_files: Vec<PathBuf>,
+) -> Result<()> {
+    // Why unsafe? See https://github.com/sayanarijit/xplr/issues/309
+    let lua = unsafe { Lua::unsafe_new() };
+    let (mut config, _) = lua::init(&lua)?;
+    for path in app::config_files(config_file, extra_config_files) {
+        (config, _) = lua::extend(&lua, &path.to_string_lossy())?;
+    }
+
+    let mut failed = 0;
+    for plugin in config.plugins.iter() {
+        let res = match command {
+            PluginCommand::Install => install(plugin),
+            PluginCommand::Update => update(plugin),
+            PluginCommand::List => status(plugin),
+        };
+
+        let source = plugin
+            .git
+            .as_ref()
+            .or(plugin.path.as_ref())
+            .cloned()
+            .unwrap_or_default();
+
+        match res {
+            Ok(status) => println!("{}\t{source}\t{status}", plugin.name),
+            Err(e) => {
+                failed += 1;
+                println!("{}\t{source}\terror: {e}", plugin.name);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} plugin(s) failed")
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_load_all() {
+        let tmp = TempDir::new("plugin").unwrap();
+        let dir = tmp.path();
+        let good = dir.join("good");
+        let old = dir.join("old");
+        fs::create_dir_all(&good).unwrap();
+        fs::create_dir_all(&old).unwrap();
+
+        fs::write(
+            good.join("init.lua"),
+            r#"
+            return {
+              setup = function(args)
+                xplr.config.general.show_hidden = args.show_hidden()
+              end,
+            }
+            "#,
+        )
+        .unwrap();
+        fs::write(old.join("init.lua"), r#"return { xplr_version = "0.1.0" }"#).unwrap();
+
+        let lua = Lua::new();
+        lua::init(&lua).unwrap();
+        lua.globals()
+            .set("dir", dir.to_string_lossy().to_string())
+            .unwrap();
+        lua.load(
+            r#"
+            xplr.config.plugins = {
+              { name = "old", path = dir .. "/old" },
+              { name = "missing", path = dir .. "/missing" },
+              {
+                name = "good",
+                path = dir .. "/good",
+                setup = { show_hidden = function() return true end },
+              },
+            }
+            "#,
+        )
+        .exec()
+        .unwrap();
+
+        let config = lua::current_config(&lua).unwrap();
+        let errs = load_all(&lua, &config.plugins);
+
+        assert_eq!(errs.len(), 2);
+        assert!(errs[0].contains("\"old\""), "{}", errs[0]);
+        assert!(errs[1].contains("\"missing\""), "{}", errs[1]);
+        assert!(lua::current_config(&lua).unwrap().general.show_hidden);
+        assert!(lua
+            .load(r#"return require("good")"#)
+            .eval::<Table>()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_config_stays_strict() {
+        let lua = Lua::new();
+        lua::init(&lua).unwrap();
+
+        let check = |script: &str| {
+            lua.load(script).exec().unwrap();
+            lua::current_config(&lua)
+        };
+
+        assert!(check(
+            r#"xplr.config.plugins = { { name = "a", setup = { f = print } } }"#
+        )
+        .is_ok());
+        assert!(
+            check(r#"xplr.config.plugins = { { name = "a", foo = print } }"#).is_err()
+        );
+        assert!(
+            check(r#"xplr.config.plugins = {}; xplr.config.general.foo = print"#)
+                .is_err()
+        );
+    }
+}
@@ -3,23 +3,33 @@
 use crate::app;
 use crate::app::ExternalMsg;
 use crate::cli::Cli;
+use crate::config::OutputFormat;
+use crate::duplicates;
 use crate::event_reader::EventReader;
 use crate::explorer;
+use crate::hash;
+use crate::input::MouseAction;
+use crate::log_file;
 use crate::lua;
 use crate::pipe;
 use crate::pwd_watcher;
+use crate::socket;
+use crate::timer;
 use crate::ui::NO_COLOR;
 use crate::ui::UI;
 use crate::yaml;
-use anyhow::{bail, Error, Result};
+use anyhow::{bail, Context, Error, Result};
+use base64::prelude::*;
 use mlua::LuaSerdeExt;
 use mlua::Value;
+use std::collections::VecDeque;
 use std::fs;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::sync::mpsc;
+use std::time::Duration;
 use tui::backend::CrosstermBackend;
 use tui::crossterm::event;
 use tui::crossterm::execute;
@@ -37,6 +47,99 @@ pub fn get_tty() -> Result<fs::File> {
     }
 }
 
+/// Handle the output message that doesn't need a terminal, adding the tasks it
+/// leads to, awaiting the async operations, and calling the commands silently.
+/// The others are returned back, for the caller to handle.
+pub(crate) fn handle_msg_out_headless(
+    mut app: app::App,
+    lua: &mlua::Lua,
+    msg: app::MsgOut,
+    tasks: &mut VecDeque<app::Task>,
+) -> Result<(app::App, Option<app::MsgOut>)> {
+    use app::MsgOut::*;
+    match msg {
+        Enqueue(task) => tasks.push_back(task),
+
+        ExplorePwdAsync => {
+            tasks.extend(wait_for(|tx| {
+                explorer::explore_async(
+                    app.explorer_config.clone(),
+                    app.pwd.clone().into(),
+                    app.focused_node().map(|n| n.relative_path.clone().into()),
+                    app.directory_buffer.as_ref().map(|d| d.focus).unwrap_or(0),
+                    tx,
+                )
+            }));
+        }
+
+        ExploreParentsAsync => {
+            tasks.extend(wait_for(|tx| {
+                explorer::explore_recursive_async(
+                    app.explorer_config.clone(),
+                    app.pwd.clone().into(),
+                    app.focused_node().map(|n| n.relative_path.clone().into()),
+                    app.directory_buffer.as_ref().map(|d| d.focus).unwrap_or(0),
+                    tx,
+                )
+            }));
+        }
+
+        FindDuplicatesAsync(path) => {
+            tasks.extend(wait_for(|tx| duplicates::find_async(path.into(), tx)));
+        }
+
+        HashAsync(paths, algo) => {
+            let paths = paths.into_iter().map(PathBuf::from).collect();
+            tasks.extend(wait_for(|tx| hash::hash_async(paths, algo, tx)));
+        }
+
+        VerifyChecksumFileAsync(path) => {
+            tasks.extend(wait_for(|tx| {
+                hash::verify_checksum_file_async(path.into(), tx)
+            }));
+        }
+
+        Debug(path) => {
+            fs::write(path, yaml::to_string(&app)?)?;
+        }
+
+        CallLua(func) | CallLuaSilently(func) | CallLuaAsync(func) => {
+            match call_lua_heavy(&app, lua, &func, true) {
+                Ok(Some(msgs)) => {
+                    app = app.handle_batch_external_msgs(msgs)?;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    app = app.log_error(err.to_string())?;
+                }
+            };
+        }
+
+        LuaEval(code) | LuaEvalSilently(code) => {
+            app = eval_lua(app, lua, &code)?;
+        }
+
+        Call(cmd) | CallSilently(cmd) => {
+            app = run_command(app, cmd, true, '\n')?;
+        }
+
+        Call0(cmd) | CallSilently0(cmd) => {
+            app = run_command(app, cmd, true, '\0')?;
+        }
+
+        msg => return Ok((app, Some(msg))),
+    }
+
+    Ok((app, None))
+}
+
+/// Start an async operation and collect the tasks it sends until it's done.
+fn wait_for(start: impl FnOnce(mpsc::Sender<app::Task>)) -> Vec<app::Task> {
+    let (tx, rx) = mpsc::channel();
+    start(tx);
+    rx.into_iter().collect()
+}
+
 // In unix system, the std::env::current_dir() calls libc getcwd() that
 // returns physical path. As a workaround, this function tries to use `PWD`
 // environment variable that is configured by shell.
@@ -60,32 +163,58 @@ fn call_lua_heavy(
 ) -> Result<Option<Vec<app::ExternalMsg>>> {
     let arg = app.to_lua_ctx_heavy();
     let arg = lua::serialize(lua, &arg)?;
-    lua::call(lua, func, arg)
+    lua::app::with_app(lua, &arg, || lua::call(lua, func, arg.clone()))
 }
 
-fn call(
+fn eval_lua(mut app: app::App, lua: &mlua::Lua, code: &str) -> Result<app::App> {
+    let res: Result<Value> = lua.load(code).eval().map_err(Error::from);
+
+    match res {
+        Ok(Value::Function(f)) => {
+            let arg = app.to_lua_ctx_heavy();
+            let res = lua.to_value(&arg).map_err(Error::from).and_then(|arg| {
+                lua::app::with_app(lua, &arg, || {
+                    let res = f.call(arg.clone())?;
+                    Ok(lua.from_value(res)?)
+                })
+            });
+            match res {
+                Ok(Some(msgs)) => {
+                    app = app.handle_batch_external_msgs(msgs)?;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    app = app.log_error(err.to_string())?;
+                }
+            }
+        }
+        Ok(v) => {
+            let res: Result<Option<Vec<ExternalMsg>>> =
+                lua.from_value(v).map_err(Error::from);
+            match res {
+                Ok(Some(msgs)) => {
+                    app = app.handle_batch_external_msgs(msgs)?;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    app = app.log_error(err.to_string())?;
+                }
+            }
+        }
+        Err(err) => {
+            app = app.log_error(err.to_string())?;
+        }
+    };
+
+    Ok(app)
+}
+
+fn run_command(
     mut app: app::App,
     cmd: app::Command,
     silent: bool,
-    terminal: &mut Terminal<CrosstermBackend<File>>,
-    event_reader: &mut EventReader,
-    mouse_enabled: &mut bool,
     delimiter: char,
 ) -> Result<app::App> {
-    if !silent {
-        if *mouse_enabled {
-            execute!(terminal.backend_mut(), event::DisableMouseCapture)
-                .unwrap_or_default();
-        }
-
-        event_reader.stop();
-
-        terminal.clear()?;
-        terminal.set_cursor_position((0, 0))?;
-        term::disable_raw_mode()?;
-        terminal.show_cursor()?;
-    }
-
     app.write_pipes(delimiter)?;
     let focus_index = app
         .directory_buffer
@@ -116,8 +245,13 @@ fn call(
         .env("XPLR_INITIAL_PWD", &app.initial_pwd)
         .env("XPLR_FOCUS_PATH", app.focused_node_str())
         .env("XPLR_FOCUS_INDEX", focus_index)
+        .env(
+            "XPLR_COUNT",
+            app.count.map(|c| c.to_string()).unwrap_or_default(),
+        )
         .env("XPLR_SESSION_PATH", &app.session_path)
         .env("XPLR_PIPE_MSG_IN", &app.pipe.msg_in)
+        .env("XPLR_SOCKET", socket_env(&app))
         .env("XPLR_PIPE_SELECTION_OUT", &app.pipe.selection_out)
         .env("XPLR_PIPE_HISTORY_OUT", &app.pipe.history_out)
         .env("XPLR_MODE", app.mode_str())
@@ -160,6 +294,34 @@ fn call(
         app = app.log_error(e)?;
     };
 
+    Ok(app)
+}
+
+fn call(
+    mut app: app::App,
+    cmd: app::Command,
+    silent: bool,
+    terminal: &mut Terminal<CrosstermBackend<File>>,
+    event_reader: &mut EventReader,
+    mouse_enabled: &mut bool,
+    delimiter: char,
+) -> Result<app::App> {
+    if !silent {
+        if *mouse_enabled {
+            execute!(terminal.backend_mut(), event::DisableMouseCapture)
+                .unwrap_or_default();
+        }
+
+        event_reader.stop();
+
+        terminal.clear()?;
+        terminal.set_cursor_position((0, 0))?;
+        term::disable_raw_mode()?;
+        terminal.show_cursor()?;
+    }
+
+    app = run_command(app, cmd, silent, delimiter)?;
+
     if !silent {
         terminal.clear()?;
         term::enable_raw_mode()?;
@@ -181,6 +343,28 @@ fn call(
     Ok(app)
 }
 
+/// Print the errors logged since the `seen` log ID to stderr, and return how
+/// many there were.
+fn print_errors(app: &app::App, seen: &mut usize) -> usize {
+    let mut errors = 0;
+    for log in app.logs.iter().skip(seen.saturating_sub(app.logs_dropped)) {
+        if log.level == app::LogLevel::Error {
+            eprintln!("error: {0}", log.message);
+            errors += 1;
+        }
+    }
+    *seen = app.logs_dropped + app.logs.len();
+    errors
+}
+
+fn socket_env(app: &app::App) -> String {
+    if app.config.general.start_socket {
+        socket::path(&app.session_path)
+    } else {
+        Default::default()
+    }
+}
+
 fn start_fifo(path: &str, focus_path: &str) -> Result<fs::File> {
     match fs::OpenOptions::new().write(true).open(path) {
         Ok(mut file) => {
@@ -201,6 +385,7 @@ pub struct Runner {
     on_load: Vec<app::ExternalMsg>,
     read_only: bool,
     print_pwd_as_result: bool,
+    output_format: Option<OutputFormat>,
     selection: Vec<PathBuf>,
     delimiter: char,
 }
@@ -236,50 +421,158 @@ impl Runner {
             on_load: cli.on_load,
             read_only: cli.read_only,
             print_pwd_as_result: cli.print_pwd_as_result,
+            output_format: cli.output_format,
             selection: paths.collect(),
             delimiter: if cli.write0 { '\0' } else { '\n' },
         })
     }
 
-    /// Run the instance
-    pub fn run(self) -> Result<Option<String>> {
-        // Why unsafe? See https://github.com/sayanarijit/xplr/issues/309
-        let lua = unsafe { mlua::Lua::unsafe_new() };
+    /// Load the config, explore the pwd, and apply the initial selection and
+    /// focus.
+    fn load_app(&self, lua: &mlua::Lua) -> Result<app::App> {
         let mut app = app::App::create(
-            self.bin,
-            self.vroot,
-            self.pwd,
-            &lua,
-            self.config_file,
-            self.extra_config_files,
+            self.bin.clone(),
+            self.vroot.clone(),
+            self.pwd.clone(),
+            lua,
+            self.config_file.clone(),
+            self.extra_config_files.clone(),
         )?;
         app.config.general.read_only = self.read_only;
 
-        fs::create_dir_all(app.session_path.clone())?;
+        lua.set_app_data(lua::ReadOnly(self.read_only));
+        if let Some(format) = self.output_format {
+            app.config.general.output_format = format;
+        }
 
-        let (tx_msg_in, rx_msg_in) = mpsc::channel();
-        let (tx_pwd_watcher, rx_pwd_watcher) = mpsc::channel();
+        fs::create_dir_all(app.session_path.clone())?;
 
         app = app.explore_pwd()?;
 
-        for file in self.selection {
+        for file in self.selection.iter() {
             app = app.select_path(file.to_string_lossy().to_string())?;
         }
 
-        app = if let Some(f) = self
+        if let Some(f) = self
             .focused_path
             .clone()
             .map(|f| f.to_string_lossy().to_string())
         {
-            app.focus_by_file_name(&f, true)?
+            app.focus_by_file_name(&f, true)
         } else {
-            app.focus_first(true)?
+            app.focus_first(true)
+        }
+    }
+
+    /// Run the instance without a terminal. It handles the `on_load` messages
+    /// and the messages read from stdin, one YAML or JSON message per line,
+    /// and prints the result when they run out, unless one of the messages
+    /// quits earlier. The async operations are awaited, and the commands are
+    /// called silently. The logged errors are printed to stderr, and fail the
+    /// run.
+    pub fn run_headless(self) -> Result<Option<String>> {
+        // Why unsafe? See https://github.com/sayanarijit/xplr/issues/309
+        let lua = unsafe { mlua::Lua::unsafe_new() };
+        let mut app = self.load_app(&lua)?;
+        let session_path = app.session_path.clone();
+
+        let mut msgs: Vec<ExternalMsg> = app.hooks.on_load.clone();
+        msgs.extend(self.on_load.iter().cloned());
+
+        let stdin = io::stdin();
+        if !stdin.is_terminal() {
+            for (i, line) in stdin.lock().lines().enumerate() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    let msg = yaml::from_str(&line)
+                        .with_context(|| format!("invalid message on line {}", i + 1))?;
+                    msgs.push(msg);
+                }
+            }
+        }
+
+        // Settle each message, with the tasks it leads to, before the next.
+        let mut msgs = msgs.into_iter();
+        let mut tasks: VecDeque<app::Task> = Default::default();
+        let mut log_file = log_file::Writer::default();
+
+        let result_str = |app: &app::App| {
+            if self.print_pwd_as_result {
+                app.pwd_str(self.delimiter)
+            } else {
+                app.result_str(self.delimiter)
+            }
+        };
+
+        let mut seen = 0;
+        let mut errors = 0;
+
+        let result = 'outer: loop {
+            errors += print_errors(&app, &mut seen);
+
+            let Some(task) = tasks.pop_front().or_else(|| {
+                msgs.next()
+                    .map(|m| app::Task::new(app::MsgIn::External(m), None))
+            }) else {
+                break Some(result_str(&app));
+            };
+
+            app = app.handle_task(task)?;
+            while let Some(msg) = app.msg_out.pop_front() {
+                use app::MsgOut::*;
+                match msg {
+                    Quit => break 'outer None,
+                    PrintPwdAndQuit => break 'outer Some(app.pwd_str(self.delimiter)),
+                    PrintFocusPathAndQuit => break 'outer app.focus_str(self.delimiter),
+                    PrintSelectionAndQuit => {
+                        break 'outer Some(app.selection_str(self.delimiter))
+                    }
+                    PrintResultAndQuit => break 'outer Some(result_str(&app)),
+                    PrintAppStateAndQuit => break 'outer Some(yaml::to_string(&app)?),
+
+                    WriteLogFile(line) => {
+                        if let Some(warning) = log_file.write(&line) {
+                            app = app.log_warning(warning)?;
+                        }
+                    }
+
+                    // Nothing else to do without a terminal.
+                    msg => {
+                        (app, _) = handle_msg_out_headless(app, &lua, msg, &mut tasks)?;
+                    }
+                }
+            }
         };
 
+        errors += print_errors(&app, &mut seen);
+        fs::remove_dir_all(session_path)?;
+
+        if errors > 0 {
+            bail!("");
+        }
+
+        Ok(result)
+    }
+
+    /// Run the instance
+    pub fn run(self) -> Result<Option<String>> {
+        // Why unsafe? See https://github.com/sayanarijit/xplr/issues/309
+        let lua = unsafe { mlua::Lua::unsafe_new() };
+
+        let (tx_msg_in, rx_msg_in) = mpsc::channel();
+        let (tx_pwd_watcher, rx_pwd_watcher) = mpsc::channel();
+        let (tx_log_file, rx_log_file) = mpsc::channel();
+
+        // Before loading the config, as it may set timers.
+        let timer = timer::Timer::start(tx_msg_in.clone());
+        lua.set_app_data(timer.clone());
+
+        let mut app = self.load_app(&lua)?;
+
         explorer::explore_recursive_async(
             app.explorer_config.clone(),
             app.pwd.clone().into(),
-            self.focused_path,
+            self.focused_path.clone(),
             app.directory_buffer.as_ref().map(|d| d.focus).unwrap_or(0),
             tx_msg_in.clone(),
         );
@@ -311,6 +604,23 @@ impl Runner {
                 None
             };
 
+        let socket_server = if app.config.general.start_socket {
+            let path = socket::path(&app.session_path);
+            match socket::Server::start(&path, tx_msg_in.clone()) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    app =
+                        app.log_error(format!("could not start socket {path:?}: {e}"))?;
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Started on the first `CallLuaAsync`.
+        let mut lua_worker: Option<lua::worker::Worker> = None;
+
         let mut last_focus: Option<app::Node> = None;
         let mut last_pwd = app.pwd.clone();
 
@@ -328,6 +638,7 @@ impl Runner {
 
         // Threads
         pwd_watcher::keep_watching(app.pwd.as_ref(), tx_msg_in.clone(), rx_pwd_watcher)?;
+        log_file::keep_writing(rx_log_file, tx_msg_in.clone());
         let mut event_reader = EventReader::new(tx_msg_in.clone());
         event_reader.start();
 
@@ -345,7 +656,30 @@ impl Runner {
         // UI
         let mut ui = UI::new(&lua);
 
-        'outer: for task in rx_msg_in {
+        'outer: for mut task in rx_msg_in {
+            if let app::MsgIn::Internal(app::InternalMsg::HandleMouse(evt)) =
+                &mut task.msg
+            {
+                // Resolve against the frame the user is looking at.
+                if evt.action == MouseAction::LeftClick {
+                    if let Some(msgs) = ui.row_action_at(evt.column, evt.row) {
+                        for msg in msgs {
+                            let msg = app::MsgIn::External(msg);
+                            tx_msg_in.send(app::Task::new(msg, None))?;
+                        }
+                        continue;
+                    }
+                }
+                evt.index = ui.node_index_at(evt.column, evt.row);
+            }
+
+            let socket_request = match &task.msg {
+                app::MsgIn::Internal(app::InternalMsg::HandleSocketRequest(id, _)) => {
+                    Some(*id)
+                }
+                _ => None,
+            };
+
             match app.handle_task(task) {
                 Ok(a) => {
                     app = a;
@@ -356,6 +690,18 @@ impl Runner {
                                 tx_msg_in.send(task)?;
                             }
 
+                            SocketReply(id, response) => {
+                                if let Some(server) = &socket_server {
+                                    server.reply(id, response);
+                                }
+                            }
+
+                            PublishEvent(event) => {
+                                if let Some(server) = &socket_server {
+                                    server.publish(&event).unwrap_or_default();
+                                }
+                            }
+
                             Quit => {
                                 result = Ok(None);
                                 break 'outer;
@@ -367,9 +713,7 @@ impl Runner {
                             }
 
                             PrintFocusPathAndQuit => {
-                                result = Ok(app.focused_node().map(|n| {
-                                    format!("{}{}", n.absolute_path, self.delimiter)
-                                }));
+                                result = Ok(app.focus_str(self.delimiter));
                                 break 'outer;
                             }
 
@@ -456,6 +800,48 @@ impl Runner {
                                 tx_pwd_watcher.send(app.pwd.clone())?;
                             }
 
+                            FindDuplicatesAsync(path) => {
+                                duplicates::find_async(path.into(), tx_msg_in.clone());
+                            }
+
+                            HashAsync(paths, algo) => {
+                                hash::hash_async(
+                                    paths.into_iter().map(PathBuf::from).collect(),
+                                    algo,
+                                    tx_msg_in.clone(),
+                                );
+                            }
+
+                            VerifyChecksumFileAsync(path) => {
+                                hash::verify_checksum_file_async(
+                                    path.into(),
+                                    tx_msg_in.clone(),
+                                );
+                            }
+
+                            CopyToClipboard(text) => {
+                                // OSC 52: Copy to clipboard
+                                write!(
+                                    terminal.backend_mut(),
+                                    "\x1b]52;c;{}\x07",
+                                    BASE64_STANDARD.encode(text)
+                                )?;
+                            }
+
+                            WriteLogFile(line) => {
+                                tx_log_file.send(line).unwrap_or_default();
+                            }
+
+                            KeySequenceTimeoutAsync(id, timeout) => {
+                                // Replaces the timer of the previous sequence.
+                                timer.schedule(
+                                    Some("key-sequence".into()),
+                                    Duration::from_millis(timeout),
+                                    None,
+                                    timer::Action::KeySequenceTimeout(id),
+                                )?;
+                            }
+
                             Refresh => {
                                 let focus = app.focused_node();
                                 if focus != last_focus.as_ref() {
@@ -466,6 +852,14 @@ impl Runner {
                                         writeln!(file, "{}", app.focused_node_str())?;
                                     };
 
+                                    // Socket
+                                    if let Some(server) = &socket_server {
+                                        let event = socket::Event::FocusChange(
+                                            last_focus.clone().map(Box::new),
+                                        );
+                                        server.publish(&event).unwrap_or_default();
+                                    }
+
                                     // Hooks
                                     if !app.hooks.on_focus_change.is_empty() {
                                         let msgs = app.hooks.on_focus_change.clone();
@@ -489,6 +883,14 @@ impl Runner {
                                         )?;
                                     }
 
+                                    // Socket
+                                    if let Some(server) = &socket_server {
+                                        let event = socket::Event::DirectoryChange(
+                                            app.pwd.clone(),
+                                        );
+                                        server.publish(&event).unwrap_or_default();
+                                    }
+
                                     // Hooks
                                     if !app.hooks.on_directory_change.is_empty() {
                                         let msgs = app.hooks.on_directory_change.clone();
@@ -530,6 +932,44 @@ impl Runner {
                                 ))?;
                             }
 
+                            CyclePanelFocus => {
+                                let focused = app.focused_panel_name();
+                                if let Some(name) = ui.next_panel(focused) {
+                                    app = app.handle_task(app::Task::new(
+                                        app::MsgIn::External(ExternalMsg::FocusPanel(
+                                            name,
+                                        )),
+                                        None,
+                                    ))?;
+                                }
+                            }
+
+                            MovePanelCursor(offset) => {
+                                ui.move_panel_cursor(app.focused_panel_name(), offset);
+                            }
+
+                            ActivatePanelRow => {
+                                let focused = app.focused_panel_name();
+                                for msg in
+                                    ui.focused_panel_action(focused).unwrap_or_default()
+                                {
+                                    let msg = app::MsgIn::External(msg);
+                                    tx_msg_in.send(app::Task::new(msg, None))?;
+                                }
+                            }
+
+                            SearchPanel(query) => {
+                                ui.search_panel(app.focused_panel_name(), query);
+                            }
+
+                            SearchPanelNext => {
+                                ui.search_panel_next(app.focused_panel_name(), false);
+                            }
+
+                            SearchPanelPrevious => {
+                                ui.search_panel_next(app.focused_panel_name(), true);
+                            }
+
                             DisableMouse => {
                                 if mouse_enabled {
                                     match execute!(
@@ -586,6 +1026,47 @@ impl Runner {
                                 }
                             }
 
+                            CallLuaAsync(func) => {
+                                let worker = lua_worker.get_or_insert_with(|| {
+                                    lua::worker::Worker::start(
+                                        app.config_files.clone(),
+                                        app.config.general.read_only,
+                                        tx_msg_in.clone(),
+                                    )
+                                });
+                                worker.call(
+                                    func,
+                                    app.to_lua_ctx_heavy(),
+                                    app.config.general.call_lua_async_timeout_ms,
+                                )?;
+                            }
+
+                            CallLuaTimer(id) => {
+                                let arg = lua::serialize(&lua, &app.to_lua_ctx_heavy())?;
+                                match timer::call_lua(&lua, &id, arg) {
+                                    Ok(Some(msgs)) => {
+                                        app = app.handle_batch_external_msgs(msgs)?;
+                                    }
+                                    Ok(None) => {}
+                                    Err(err) => {
+                                        app = app.log_error(err.to_string())?;
+                                    }
+                                };
+                            }
+
+                            EnqueueAfter(delayed) => {
+                                timer.schedule(
+                                    delayed.id,
+                                    Duration::from_millis(delayed.delay_ms),
+                                    None,
+                                    timer::Action::Enqueue(delayed.messages),
+                                )?;
+                            }
+
+                            CancelTimer(id) => {
+                                timer::cancel_lua(&lua, id)?;
+                            }
+
                             CallLuaSilently(func) => {
                                 match call_lua_heavy(&app, &lua, &func, false) {
                                     Ok(Some(msgs)) => {
@@ -656,46 +1137,7 @@ impl Runner {
                                 term::disable_raw_mode()?;
                                 terminal.show_cursor()?;
 
-                                let res: Result<Value> =
-                                    lua.load(&code).eval().map_err(Error::from);
-
-                                match res {
-                                    Ok(Value::Function(f)) => {
-                                        let arg = app.to_lua_ctx_heavy();
-                                        let res: Result<Option<Vec<ExternalMsg>>> = lua
-                                            .to_value(&arg)
-                                            .and_then(|a| f.call(a))
-                                            .and_then(|v| lua.from_value(v))
-                                            .map_err(Error::from);
-                                        match res {
-                                            Ok(Some(msgs)) => {
-                                                app = app
-                                                    .handle_batch_external_msgs(msgs)?;
-                                            }
-                                            Ok(None) => {}
-                                            Err(err) => {
-                                                app = app.log_error(err.to_string())?;
-                                            }
-                                        }
-                                    }
-                                    Ok(v) => {
-                                        let res: Result<Option<Vec<ExternalMsg>>> =
-                                            lua.from_value(v).map_err(Error::from);
-                                        match res {
-                                            Ok(Some(msgs)) => {
-                                                app = app
-                                                    .handle_batch_external_msgs(msgs)?;
-                                            }
-                                            Ok(None) => {}
-                                            Err(err) => {
-                                                app = app.log_error(err.to_string())?;
-                                            }
-                                        }
-                                    }
-                                    Err(err) => {
-                                        app = app.log_error(err.to_string())?;
-                                    }
-                                };
+                                app = eval_lua(app, &lua, &code)?;
 
                                 terminal.clear()?;
                                 term::enable_raw_mode()?;
@@ -718,46 +1160,7 @@ impl Runner {
                             }
 
                             LuaEvalSilently(code) => {
-                                let res: Result<Value> =
-                                    lua.load(&code).eval().map_err(Error::from);
-
-                                match res {
-                                    Ok(Value::Function(f)) => {
-                                        let arg = app.to_lua_ctx_heavy();
-                                        let res: Result<Option<Vec<ExternalMsg>>> = lua
-                                            .to_value(&arg)
-                                            .and_then(|a| f.call(a))
-                                            .and_then(|v| lua.from_value(v))
-                                            .map_err(Error::from);
-                                        match res {
-                                            Ok(Some(msgs)) => {
-                                                app = app
-                                                    .handle_batch_external_msgs(msgs)?;
-                                            }
-                                            Ok(None) => {}
-                                            Err(err) => {
-                                                app = app.log_error(err.to_string())?;
-                                            }
-                                        }
-                                    }
-                                    Ok(v) => {
-                                        let res: Result<Option<Vec<ExternalMsg>>> =
-                                            lua.from_value(v).map_err(Error::from);
-                                        match res {
-                                            Ok(Some(msgs)) => {
-                                                app = app
-                                                    .handle_batch_external_msgs(msgs)?;
-                                            }
-                                            Ok(None) => {}
-                                            Err(err) => {
-                                                app = app.log_error(err.to_string())?;
-                                            }
-                                        }
-                                    }
-                                    Err(err) => {
-                                        app = app.log_error(err.to_string())?;
-                                    }
-                                };
+                                app = eval_lua(app, &lua, &code)?;
                             }
 
                             Call(cmd) => {
@@ -812,12 +1215,22 @@ impl Runner {
                 }
 
                 Err(e) => {
+                    // Don't leave the client waiting.
+                    if let (Some(id), Some(server)) = (socket_request, &socket_server) {
+                        let response = socket::Response::Err(e.to_string());
+                        server.reply(id, serde_json::to_string(&response)?);
+                    }
                     result = Err(e);
                     break;
                 }
             }
         }
 
+        if let Some(server) = socket_server {
+            server.publish(&socket::Event::Quit).unwrap_or_default();
+            server.stop();
+        }
+
         terminal.clear()?;
         terminal.set_cursor_position((0, 0))?;
         execute!(terminal.backend_mut(), term::LeaveAlternateScreen)?;
@@ -0,0 +1,335 @@
+use crate::app::{ExternalMsg, InternalMsg, MsgIn, Node, Task};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A request read from the socket, one JSON value per line.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Request {
+    GetPwd,
+    GetSelection,
+    GetFocusedNode,
+    GetAppState,
+    Subscribe,
+
+    #[serde(untagged)]
+    Msg(ExternalMsg),
+}
+
+/// The reply written back to the socket for each request, as a JSON line,
+/// e.g. `{"Ok":null}` or `{"Err":"..."}`.
+pub type Response = Result<serde_json::Value, String>;
+
+/// An event written to the subscribed connections, as a JSON line. They are
+/// emitted on the same triggers as the corresponding hooks.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    FocusChange(Option<Box<Node>>),
+    DirectoryChange(String),
+    SelectionChange(Vec<Node>),
+    ModeSwitch(String),
+    Quit,
+}
+
+/// How long to wait for xplr to handle a request, e.g. while it's running a
+/// command, before replying with an error.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+struct Shared {
+    replies: Arc<Mutex<HashMap<usize, Sender<String>>>>,
+    subscribers: Arc<Mutex<Vec<UnixStream>>>,
+    next_id: Arc<AtomicUsize>,
+    reply_timeout: Duration,
+}
+
+pub fn path(session_path: &str) -> String {
+    PathBuf::from(session_path)
+        .join("socket")
+        .to_string_lossy()
+        .to_string()
+}
+
+pub struct Server {
+    shared: Shared,
+    tx_events: Sender<String>,
+    writer: thread::JoinHandle<()>,
+}
+
+impl Server {
+    /// Listen on the given path, forwarding each request to the main loop.
+    pub fn start(path: &str, tx_msg_in: Sender<Task>) -> Result<Self> {
+        Self::start_with_timeout(path, tx_msg_in, REPLY_TIMEOUT)
+    }
+
+    fn start_with_timeout(
+        path: &str,
+        tx_msg_in: Sender<Task>,
+        reply_timeout: Duration,
+    ) -> Result<Self> {
+        if fs::symlink_metadata(path).is_ok() {
+            fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        let shared = Shared {
+            replies: Default::default(),
+            subscribers: Default::default(),
+            next_id: Default::default(),
+            reply_timeout,
+        };
+
+        let server_shared = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let tx_msg_in = tx_msg_in.clone();
+                let shared = server_shared.clone();
+                thread::spawn(move || serve(stream, tx_msg_in, shared));
+            }
+        });
+
+        // The events are written on a thread of its own, so that a slow
+        // subscriber doesn't hold up the UI.
+        let (tx_events, rx_events) = mpsc::channel::<String>();
+        let subscribers = shared.subscribers.clone();
+        let writer = thread::spawn(move || {
+            for line in rx_events {
+                if let Ok(mut subscribers) = subscribers.lock() {
+                    subscribers.retain_mut(|s| writeln!(s, "{line}").is_ok());
+                }
+            }
+        });
+
+        Ok(Self {
+            shared,
+            tx_events,
+            writer,
+        })
+    }
+
+    pub fn reply(&self, id: usize, response: String) {
+        let tx = self
+            .shared
+            .replies
+            .lock()
+            .ok()
+            .and_then(|mut r| r.remove(&id));
+        if let Some(tx) = tx {
+            tx.send(response).unwrap_or_default();
+        }
+    }
+
+    /// Queue the event to be written to the subscribers. The ones that can't
+    /// keep up or have disconnected are dropped.
+    pub fn publish(&self, event: &Event) -> Result<()> {
+        let line = serde_json::to_string(event)?;
+        self.tx_events.send(line)?;
+        Ok(())
+    }
+
+    /// Wait for the queued events to be written.
+    pub fn stop(self) {
+        drop(self.tx_events);
+        self.writer.join().unwrap_or_default();
+    }
+}
+
+fn serve(stream: UnixStream, tx_msg_in: Sender<Task>, shared: Shared) -> Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Subscribe) => {
+                // The connection is dedicated to the events from now on.
+                writeln!(
+                    writer,
+                    "{}",
+                    serde_json::to_string(&Response::Ok(().into()))?
+                )?;
+                writer.set_write_timeout(Some(Duration::from_millis(100)))?;
+                if let Ok(mut subscribers) = shared.subscribers.lock() {
+                    subscribers.push(writer);
+                }
+                return Ok(());
+            }
+            Ok(req) => {
+                // A channel per request, so that a late reply isn't taken for
+                // the next one's.
+                let (tx_reply, rx_reply) = mpsc::channel();
+                let id = shared.next_id.fetch_add(1, Ordering::Relaxed);
+                if let Ok(mut r) = shared.replies.lock() {
+                    r.insert(id, tx_reply);
+                }
+
+                let msg = MsgIn::Internal(InternalMsg::HandleSocketRequest(id, req));
+                tx_msg_in.send(Task::new(msg, None)).unwrap_or_default(); // Let's not panic if xplr closes.
+                match rx_reply.recv_timeout(shared.reply_timeout) {
+                    Ok(response) => response,
+                    Err(_) => {
+                        if let Ok(mut r) = shared.replies.lock() {
+                            r.remove(&id);
+                        }
+                        let err = "timed out waiting for xplr to handle the request";
+                        serde_json::to_string(&Response::Err(err.into()))?
+                    }
+                }
+            }
+            Err(e) => {
+                serde_json::to_string(&Response::Err(format!("invalid request: {e}")))?
+            }
+        };
+
+        writeln!(writer, "{response}")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_parse_request() {
+        let parse = |s: &str| serde_json::from_str::<Request>(s).unwrap();
+
+        assert_eq!(parse(r#""GetPwd""#), Request::GetPwd);
+        assert_eq!(parse(r#""Subscribe""#), Request::Subscribe);
+        assert_eq!(
+            parse(r#""FocusNext""#),
+            Request::Msg(ExternalMsg::FocusNext)
+        );
+        assert_eq!(
+            parse(r#"{"ChangeDirectory":"/tmp"}"#),
+            Request::Msg(ExternalMsg::ChangeDirectory("/tmp".into()))
+        );
+        assert!(serde_json::from_str::<Request>(r#""NoSuchThing""#).is_err());
+    }
+
+    #[test]
+    fn test_server() {
+        let dir = TempDir::new("socket_server").unwrap();
+        let path = path(&dir.path().to_string_lossy());
+
+        let (tx_msg_in, rx_msg_in) = mpsc::channel();
+        let server = Server::start(&path, tx_msg_in).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        let mut lines = BufReader::new(client.try_clone().unwrap()).lines();
+
+        writeln!(client, "not json").unwrap();
+        assert!(lines
+            .next()
+            .unwrap()
+            .unwrap()
+            .starts_with(r#"{"Err":"invalid request"#));
+
+        writeln!(client, r#""GetPwd""#).unwrap();
+        let task = rx_msg_in.recv().unwrap();
+        let MsgIn::Internal(InternalMsg::HandleSocketRequest(id, req)) = task.msg else {
+            panic!("unexpected task: {task:?}");
+        };
+        assert_eq!(req, Request::GetPwd);
+
+        server.reply(id, r#"{"Ok":"/tmp"}"#.into());
+        assert_eq!(lines.next().unwrap().unwrap(), r#"{"Ok":"/tmp"}"#);
+        assert!(server.shared.replies.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reply_timeout() {
+        let dir = TempDir::new("socket_reply_timeout").unwrap();
+        let path = path(&dir.path().to_string_lossy());
+
+        let (tx_msg_in, rx_msg_in) = mpsc::channel();
+        let server =
+            Server::start_with_timeout(&path, tx_msg_in, Duration::from_millis(50))
+                .unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        let mut lines = BufReader::new(client.try_clone().unwrap()).lines();
+
+        // Never replied.
+        writeln!(client, r#""GetPwd""#).unwrap();
+        let task = rx_msg_in.recv().unwrap();
+        let MsgIn::Internal(InternalMsg::HandleSocketRequest(late, _)) = task.msg else {
+            panic!("unexpected task: {task:?}");
+        };
+        assert!(lines
+            .next()
+            .unwrap()
+            .unwrap()
+            .starts_with(r#"{"Err":"timed out"#));
+        assert!(server.shared.replies.lock().unwrap().is_empty());
+
+        // The late reply isn't taken for the next request's.
+        server.reply(late, r#"{"Ok":"late"}"#.into());
+        writeln!(client, r#""GetPwd""#).unwrap();
+        let task = rx_msg_in.recv().unwrap();
+        let MsgIn::Internal(InternalMsg::HandleSocketRequest(id, _)) = task.msg else {
+            panic!("unexpected task: {task:?}");
+        };
+        server.reply(id, r#"{"Ok":"/tmp"}"#.into());
+        assert_eq!(lines.next().unwrap().unwrap(), r#"{"Ok":"/tmp"}"#);
+    }
+
+    #[test]
+    fn test_events() {
+        let dir = TempDir::new("socket_events").unwrap();
+        let path = path(&dir.path().to_string_lossy());
+
+        let (tx_msg_in, _rx_msg_in) = mpsc::channel();
+        let server = Server::start(&path, tx_msg_in).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        let mut lines = BufReader::new(client.try_clone().unwrap()).lines();
+        writeln!(client, r#""Subscribe""#).unwrap();
+        assert_eq!(lines.next().unwrap().unwrap(), r#"{"Ok":null}"#);
+
+        // A subscriber that doesn't read doesn't stop the others from getting
+        // the events.
+        let mut slow = UnixStream::connect(&path).unwrap();
+        writeln!(slow, r#""Subscribe""#).unwrap();
+
+        while server.shared.subscribers.lock().unwrap().len() < 2 {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        server
+            .publish(&Event::DirectoryChange("/tmp".into()))
+            .unwrap();
+        server.publish(&Event::ModeSwitch("action".into())).unwrap();
+        server.publish(&Event::Quit).unwrap();
+        server.stop();
+
+        let events = lines
+            .map(|l| serde_json::from_str::<Event>(&l.unwrap()).unwrap())
+            .take(3)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::DirectoryChange("/tmp".into()),
+                Event::ModeSwitch("action".into()),
+                Event::Quit,
+            ]
+        );
+    }
+}
@@ -0,0 +1,720 @@
+//! A harness to test the key bindings, modes, layouts and plugins end to end.
+//! It drives the app with keys and messages, without a terminal, and renders
+//! it into a fake one.
+//!
+//! ```
+//! use xplr::testing::Harness;
+//!
+//! let lua = mlua::Lua::new();
+//! let xplr = Harness::new(&lua, "src", None)
+//!     .and_then(|h| h.press_keys(&["G", "g", "g"]))
+//!     .unwrap();
+//!
+//! assert_eq!(xplr.app.directory_buffer.as_ref().unwrap().focus, 0);
+//! assert!(xplr.screen().contains("app.rs"));
+//! ```
+
+use crate::app::{App, ExternalMsg, InternalMsg, MsgIn, MsgOut, Task};
+use crate::input::Key;
+use crate::runner;
+use crate::ui::UI;
+use anyhow::{bail, Result};
+use mlua::Lua;
+use path_absolutize::*;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tui::backend::TestBackend;
+use tui::buffer::Buffer;
+use tui::Terminal;
+
+pub struct Harness<'lua> {
+    pub app: App,
+    pub ui: UI<'lua>,
+    lua: &'lua Lua,
+    terminal: Terminal<TestBackend>,
+
+    /// The message that quit xplr, if any.
+    pub exit: Option<MsgOut>,
+}
+
+impl<'lua> Harness<'lua> {
+    /// Load the default config, and the config file if given, in the given
+    /// directory, and render it in a 80x24 terminal. The user's config is
+    /// never loaded, so that the tests behave the same everywhere. The async
+    /// operations are awaited, and the commands are called silently, like in
+    /// the headless mode.
+    pub fn new(
+        lua: &'lua Lua,
+        pwd: impl Into<PathBuf>,
+        config_file: Option<PathBuf>,
+    ) -> Result<Self> {
+        let Some(config_file) = config_file else {
+            return Self::with_config(lua, pwd, "");
+        };
+
+        let pwd = pwd.into().absolutize()?.to_path_buf();
+        let app = App::create("xplr".into(), None, pwd, lua, Some(config_file), vec![])?
+            .explore_pwd()?
+            .focus_first(true)?;
+
+        lua.set_app_data(crate::lua::ReadOnly(app.config.general.read_only));
+
+        let on_load = app.hooks.on_load.clone();
+        let mut harness = Self {
+            app,
+            ui: UI::new(lua),
+            lua,
+            terminal: Terminal::new(TestBackend::new(80, 24))?,
+            exit: None,
+        };
+
+        for msg in on_load {
+            harness = harness.send(msg)?;
+        }
+
+        harness.send(ExternalMsg::Refresh)
+    }
+
+    /// Like `new`, but with the config given as Lua code.
+    pub fn with_config(
+        lua: &'lua Lua,
+        pwd: impl Into<PathBuf>,
+        config: &str,
+    ) -> Result<Self> {
+        let dir = TempDir::new("harness")?;
+        let path = dir.path().join("init.lua");
+        fs::write(
+            &path,
+            format!("version = {0:?}\n{config}", crate::app::VERSION),
+        )?;
+        Self::new(lua, pwd, Some(path))
+    }
+
+    /// Press the key, given by its name, e.g. "j", "ctrl-a" or "space".
+    pub fn press(self, key: &str) -> Result<Self> {
+        let key = Key::from(key);
+        if key == Key::NotSupported {
+            bail!("key not supported: {key:?}")
+        }
+        self.handle(Task::new(
+            MsgIn::Internal(InternalMsg::HandleKey(key)),
+            Some(key),
+        ))
+    }
+
+    pub fn press_keys(self, keys: &[&str]) -> Result<Self> {
+        keys.iter().try_fold(self, |h, key| h.press(key))
+    }
+
+    pub fn send(self, msg: ExternalMsg) -> Result<Self> {
+        self.handle(Task::new(MsgIn::External(msg), None))
+    }
+
+    pub fn resize(mut self, width: u16, height: u16) -> Result<Self> {
+        self.terminal.backend_mut().resize(width, height);
+        self.send(ExternalMsg::Refresh)
+    }
+
+    /// The last rendered frame.
+    pub fn buffer(&self) -> &Buffer {
+        self.terminal.backend().buffer()
+    }
+
+    /// The text of the last rendered frame, without the trailing spaces.
+    pub fn screen(&self) -> String {
+        let buf = self.buffer();
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .filter_map(|x| buf.cell((x, y)).map(|c| c.symbol()))
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Handle the task, and the tasks it leads to.
+    fn handle(mut self, task: Task) -> Result<Self> {
+        if let Some(msg) = &self.exit {
+            bail!("xplr already quit with {msg:?}")
+        }
+
+        let mut tasks = VecDeque::from([task]);
+        while let Some(task) = tasks.pop_front() {
+            self.app = self.app.handle_task(task)?;
+            while let Some(msg) = self.app.msg_out.pop_front() {
+                let unhandled;
+                (self.app, unhandled) = runner::handle_msg_out_headless(
+                    self.app, self.lua, msg, &mut tasks,
+                )?;
+
+                let height = self.terminal.size()?.height as usize;
+
+                use MsgOut::*;
+                match unhandled {
+                    Some(Refresh) => {
+                        self.terminal.draw(|f| self.ui.draw(f, &self.app))?;
+                    }
+
+                    Some(ScrollUp) => {
+                        self.app = self.app.focus_previous_by_relative_index(height)?;
+                    }
+
+                    Some(ScrollDown) => {
+                        self.app = self.app.focus_next_by_relative_index(height)?;
+                    }
+
+                    Some(ScrollUpHalf) => {
+                        self.app =
+                            self.app.focus_previous_by_relative_index(height / 2)?;
+                    }
+
+                    Some(ScrollDownHalf) => {
+                        self.app = self.app.focus_next_by_relative_index(height / 2)?;
+                    }
+
+                    Some(CyclePanelFocus) => {
+                        if let Some(name) =
+                            self.ui.next_panel(self.app.focused_panel_name())
+                        {
+                            let msg = MsgIn::External(ExternalMsg::FocusPanel(name));
+                            tasks.push_back(Task::new(msg, None));
+                        }
+                    }
+
+                    Some(MovePanelCursor(offset)) => {
+                        self.ui
+                            .move_panel_cursor(self.app.focused_panel_name(), offset);
+                    }
+
+                    Some(ActivatePanelRow) => {
+                        let focused = self.app.focused_panel_name();
+                        for msg in
+                            self.ui.focused_panel_action(focused).unwrap_or_default()
+                        {
+                            tasks.push_back(Task::new(MsgIn::External(msg), None));
+                        }
+                    }
+
+                    Some(SearchPanel(query)) => {
+                        self.ui.search_panel(self.app.focused_panel_name(), query);
+                    }
+
+                    Some(SearchPanelNext) => {
+                        self.ui
+                            .search_panel_next(self.app.focused_panel_name(), false);
+                    }
+
+                    Some(SearchPanelPrevious) => {
+                        self.ui
+                            .search_panel_next(self.app.focused_panel_name(), true);
+                    }
+
+                    Some(
+                        msg @ (Quit
+                        | PrintPwdAndQuit
+                        | PrintFocusPathAndQuit
+                        | PrintSelectionAndQuit
+                        | PrintResultAndQuit
+                        | PrintAppStateAndQuit),
+                    ) => {
+                        self.exit = Some(msg);
+                        return Ok(self);
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// A new directory for the test files, removed when dropped, even if the test
+/// panics.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "xplr_test_{name}_{0}_{1}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // xplr changes the working directory, so the path must be absolute.
+    const PWD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+    #[test]
+    fn test_harness() {
+        let lua = Lua::new();
+        let config = r#"
+            xplr.config.modes.builtin.default.key_bindings.on_key["x"] = {
+              help = "greet",
+              messages = { { LogSuccess = "hello from x" } },
+            }
+        "#;
+
+        let xplr = Harness::with_config(&lua, PWD, config)
+            .and_then(|h| h.press_keys(&["j", "space", "x"]))
+            .unwrap();
+
+        assert_eq!(xplr.app.directory_buffer.as_ref().unwrap().focus, 2);
+        assert_eq!(xplr.app.selection.len(), 1);
+        assert!(xplr.screen().contains("hello from x"));
+
+        let xplr = xplr.resize(40, 10).and_then(|h| h.press("enter")).unwrap();
+        assert_eq!(xplr.buffer().area.width, 40);
+        assert_eq!(xplr.exit, Some(MsgOut::PrintResultAndQuit));
+        assert!(xplr.press("j").is_err());
+    }
+
+    #[test]
+    fn test_harness_skips_user_config() {
+        let lua = Lua::new();
+        let xplr = Harness::new(&lua, PWD, None).unwrap();
+
+        // Only the empty config file of the harness.
+        let user_config = crate::dirs::config_dir().map(|d| d.join("xplr/init.lua"));
+        assert_eq!(xplr.app.config_files.len(), 1);
+        assert_ne!(xplr.app.config_files.first(), user_config.as_ref());
+        assert!(xplr.app.config_files[0].starts_with(std::env::temp_dir()));
+    }
+
+    fn messages(xplr: &Harness) -> Vec<String> {
+        xplr.app.logs.iter().map(|l| l.message.clone()).collect()
+    }
+
+    fn timeout(xplr: Harness, id: usize) -> Harness {
+        let msg = MsgIn::Internal(InternalMsg::KeySequenceTimeout(id));
+        xplr.handle(Task::new(msg, None)).unwrap()
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        let lua = Lua::new();
+        let config = r#"
+            local on_key = xplr.config.modes.builtin.default.key_bindings.on_key
+            on_key["x"] = { messages = { { LogSuccess = "x" } } }
+            on_key["x  y"] = { messages = { { LogSuccess = "x y" } } }
+            on_key["z y"] = { messages = { { LogSuccess = "z y" } } }
+        "#;
+
+        // The whole sequence matches, regardless of the spaces.
+        let xplr = Harness::with_config(&lua, PWD, config)
+            .and_then(|h| h.press_keys(&["x", "y"]))
+            .unwrap();
+        assert_eq!(messages(&xplr), vec!["x y"]);
+        assert!(xplr.app.pending_keys.is_empty());
+
+        // An ambiguous prefix waits for the next key.
+        let xplr = xplr.press("x").unwrap();
+        assert_eq!(messages(&xplr), vec!["x y"]);
+        assert_eq!(xplr.app.pending_keys, vec![Key::from("x")]);
+
+        // A stale timeout is ignored.
+        let id = xplr.app.pending_keys_id;
+        let xplr = timeout(xplr, id - 1);
+        assert_eq!(xplr.app.pending_keys, vec![Key::from("x")]);
+
+        // The timeout handles the prefix as a key of its own.
+        let xplr = timeout(xplr, id);
+        assert_eq!(messages(&xplr), vec!["x y", "x"]);
+        assert!(xplr.app.pending_keys.is_empty());
+    }
+
+    #[test]
+    fn test_broken_key_sequence() {
+        let lua = Lua::new();
+        let config = r#"
+            local on_key = xplr.config.modes.builtin.default.key_bindings.on_key
+            on_key["x"] = { messages = { { LogSuccess = "x" } } }
+            on_key["x y"] = { messages = { { LogSuccess = "x y" } } }
+            on_key["z y"] = { messages = { { LogSuccess = "z y" } } }
+        "#;
+
+        // The pending keys are handled one by one, then the next key.
+        let xplr = Harness::with_config(&lua, PWD, config)
+            .and_then(|h| h.press_keys(&["x", "j"]))
+            .unwrap();
+        assert_eq!(messages(&xplr), vec!["x"]);
+        assert_eq!(xplr.app.directory_buffer.as_ref().unwrap().focus, 1);
+        assert!(xplr.app.pending_keys.is_empty());
+
+        // A prefix that isn't bound on its own falls back to the default
+        // action.
+        let xplr = xplr.press_keys(&["z", "k"]).unwrap();
+        assert_eq!(messages(&xplr), vec!["x", "key map not found."]);
+        assert_eq!(xplr.app.directory_buffer.as_ref().unwrap().focus, 0);
+        assert!(xplr.app.pending_keys.is_empty());
+    }
+
+    fn focus(xplr: &Harness) -> usize {
+        xplr.app.directory_buffer.as_ref().unwrap().focus
+    }
+
+    #[test]
+    fn test_count() {
+        let lua = Lua::new();
+        let xplr = Harness::new(&lua, PWD, None).unwrap();
+
+        // Leading zeros are ignored, and the digits add up.
+        let xplr = xplr.press_keys(&["0", "1", "2"]).unwrap();
+        assert_eq!(xplr.app.count, Some(12));
+
+        // The count is passed to the next action only.
+        let xplr = xplr.press("j").unwrap();
+        assert_eq!(focus(&xplr), 12);
+        assert_eq!(xplr.app.count, None);
+        let xplr = xplr.press("k").unwrap();
+        assert_eq!(focus(&xplr), 11);
+
+        // It's cleared by an action that doesn't use it.
+        let xplr = xplr.press_keys(&["3", ".", "."]).unwrap();
+        assert_eq!(xplr.app.count, None);
+
+        let xplr = xplr.press_keys(&["3", "space"]).unwrap();
+        assert_eq!(focus(&xplr), 14);
+        assert_eq!(xplr.app.selection.len(), 3);
+
+        // The count multiplies the repetitions.
+        let xplr = xplr
+            .send(ExternalMsg::SetCount(2))
+            .and_then(|h| {
+                h.send(ExternalMsg::Repeat(3, vec![ExternalMsg::FocusPrevious]))
+            })
+            .and_then(|h| h.send(ExternalMsg::ClearCount))
+            .unwrap();
+        assert_eq!(focus(&xplr), 8);
+
+        // It goes to the index, else to the bottom.
+        let xplr = xplr.press_keys(&["5", "G"]).unwrap();
+        assert_eq!(focus(&xplr), 5);
+        let xplr = xplr.press("G").unwrap();
+        let total = xplr.app.directory_buffer.as_ref().unwrap().total;
+        assert_eq!(focus(&xplr), total - 1);
+
+        // The number mode is still available.
+        let xplr = xplr.press_keys(&[":", "4", "enter"]).unwrap();
+        assert_eq!(focus(&xplr), 4);
+        assert_eq!(xplr.app.mode.name, "default");
+    }
+
+    #[test]
+    fn test_repeat_last_action() {
+        let lua = Lua::new();
+        let xplr = Harness::new(&lua, PWD, None)
+            .and_then(|h| h.press_keys(&["space", "j", ","]))
+            .unwrap();
+
+        // The navigation isn't recorded, the selection is.
+        assert_eq!(focus(&xplr), 3);
+        assert_eq!(xplr.app.selection.len(), 2);
+
+        // The count is passed too.
+        let xplr = xplr.press_keys(&["2", ","]).unwrap();
+        assert_eq!(focus(&xplr), 5);
+        assert_eq!(xplr.app.selection.len(), 4);
+    }
+
+    #[test]
+    fn test_logs() {
+        use crate::app::LogLevel;
+
+        let lua = Lua::new();
+        let config = "xplr.config.general.logs.max_entries = 3";
+        let xplr = ["a", "b", "c", "d"]
+            .into_iter()
+            .try_fold(Harness::with_config(&lua, PWD, config).unwrap(), |h, m| {
+                h.send(ExternalMsg::LogInfo(m.into()))
+            })
+            .and_then(|h| h.send(ExternalMsg::LogError("e".into())))
+            .unwrap();
+
+        // The oldest logs are dropped, keeping the IDs of the others.
+        let logs = xplr
+            .app
+            .filtered_logs()
+            .map(|(i, l)| (i, l.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(logs, vec![(2, "c"), (3, "d"), (4, "e")]);
+
+        let xplr = xplr
+            .send(ExternalMsg::SetLogLevelFilter(LogLevel::Error))
+            .unwrap();
+        let logs = xplr.app.filtered_logs().map(|(i, _)| i).collect::<Vec<_>>();
+        assert_eq!(logs, vec![4]);
+
+        let copy = |id| {
+            let msg = MsgIn::External(ExternalMsg::CopyLog(id));
+            xplr.app.clone().handle_task(Task::new(msg, None)).unwrap()
+        };
+
+        let app = copy(3);
+        let copied = app.msg_out.iter().find_map(|m| match m {
+            MsgOut::CopyToClipboard(text) => Some(text.clone()),
+            _ => None,
+        });
+        assert!(copied.unwrap().ends_with(" d"));
+
+        // The dropped logs can't be copied.
+        let app = copy(1);
+        assert!(!app
+            .msg_out
+            .iter()
+            .any(|m| matches!(m, MsgOut::CopyToClipboard(_))));
+        assert_eq!(
+            app.logs.last().unwrap().message,
+            "log 1 is no longer available"
+        );
+    }
+
+    #[test]
+    fn test_socket_requests() {
+        use crate::socket::Request;
+
+        let lua = Lua::new();
+        let config = "xplr.config.general.logs.max_entries = 1";
+        let xplr = Harness::with_config(&lua, PWD, config)
+            .and_then(|h| h.send(ExternalMsg::LogInfo("before".into())))
+            .unwrap();
+
+        let request = |req| {
+            let msg = MsgIn::Internal(InternalMsg::HandleSocketRequest(7, req));
+            let app = xplr.app.clone().handle_task(Task::new(msg, None)).unwrap();
+            app.msg_out
+                .into_iter()
+                .filter(|m| matches!(m, MsgOut::SocketReply(..) | MsgOut::Quit))
+                .collect::<Vec<_>>()
+        };
+
+        let reply = |res: crate::socket::Response| {
+            MsgOut::SocketReply(7, serde_json::to_string(&res).unwrap())
+        };
+
+        assert_eq!(request(Request::GetPwd), vec![reply(Ok(PWD.into()))]);
+
+        // The errors are reported, even if the old logs are dropped.
+        let msg = ExternalMsg::LogError("boom".into());
+        assert_eq!(request(Request::Msg(msg)), vec![reply(Err("boom".into()))]);
+
+        let msg = ExternalMsg::LogSuccess("done".into());
+        assert_eq!(request(Request::Msg(msg)), vec![reply(Ok(().into()))]);
+
+        // The reply is sent before quitting.
+        assert_eq!(
+            request(Request::Msg(ExternalMsg::Quit)),
+            vec![reply(Ok(().into())), MsgOut::Quit]
+        );
+    }
+
+    #[test]
+    fn test_output_format() {
+        let lua = Lua::new();
+        let config = r#"xplr.config.general.output_format = "Json""#;
+        let xplr = Harness::with_config(&lua, PWD, config)
+            .and_then(|h| h.press("space"))
+            .unwrap();
+
+        let path = xplr.app.selection.first().unwrap().absolute_path.clone();
+        xplr.app.write_pipes('\n').unwrap();
+        let read = |pipe: &str| fs::read_to_string(pipe).unwrap();
+
+        // The pipes get the full nodes too.
+        for pipe in [&xplr.app.pipe.selection_out, &xplr.app.pipe.result_out] {
+            let nodes: serde_json::Value = serde_json::from_str(&read(pipe)).unwrap();
+            assert_eq!(nodes.as_array().unwrap().len(), 1);
+            assert_eq!(nodes[0]["absolute_path"], path.as_str());
+        }
+
+        let lua = Lua::new();
+        let config = r#"xplr.config.general.output_format = "Jsonl""#;
+        let xplr = Harness::with_config(&lua, PWD, config).unwrap();
+        xplr.app.write_pipes('\n').unwrap();
+
+        let nodes = read(&xplr.app.pipe.directory_nodes_out)
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .collect::<Vec<_>>();
+        let dir = xplr.app.directory_buffer.as_ref().unwrap();
+        assert_eq!(nodes.len(), dir.nodes.len());
+        assert_eq!(
+            nodes[0]["absolute_path"],
+            dir.nodes[0].absolute_path.as_str()
+        );
+    }
+
+    #[test]
+    fn test_copy_hash_failure() {
+        use crate::hash::{HashAlgorithm, NodeHash};
+
+        let lua = Lua::new();
+        let xplr = Harness::new(&lua, PWD, None).unwrap();
+        let path = format!("{PWD}/app.rs");
+
+        let mut app = xplr.app;
+        app.hash_to_copy = Some((path.clone(), HashAlgorithm::Sha256));
+        let hash = NodeHash {
+            absolute_path: path.clone(),
+            size: 0,
+            last_modified: None,
+            algo: HashAlgorithm::Sha256,
+            digest: None,
+            checksum: None,
+            error: Some("permission denied".into()),
+        };
+
+        let msg = MsgIn::Internal(InternalMsg::SetNodeHashes(vec![hash.clone()]));
+        let app = app.handle_task(Task::new(msg, None)).unwrap();
+        assert_eq!(app.hash_to_copy, None);
+        assert!(app
+            .logs
+            .last()
+            .unwrap()
+            .message
+            .contains("permission denied"));
+
+        // A later hash of the same file isn't copied.
+        let hash = NodeHash {
+            digest: Some("abc".into()),
+            error: None,
+            ..hash
+        };
+        let msg = MsgIn::Internal(InternalMsg::SetNodeHashes(vec![hash]));
+        let app = app.handle_task(Task::new(msg, None)).unwrap();
+        assert!(!app
+            .msg_out
+            .iter()
+            .any(|m| matches!(m, MsgOut::CopyToClipboard(_))));
+    }
+
+    #[test]
+    fn test_macros() {
+        let lua = Lua::new();
+        let focus = |xplr: &Harness| xplr.app.directory_buffer.as_ref().unwrap().focus;
+
+        let xplr = Harness::new(&lua, PWD, None)
+            .and_then(|h| h.press_keys(&["Q", "a", "j", "j", "Q", "Q"]))
+            .unwrap();
+        assert_eq!(focus(&xplr), 2);
+        assert_eq!(xplr.app.macros["a"], vec!["j", "j"]);
+
+        let saved = fs::read_to_string(xplr.app.macros_file()).unwrap();
+        assert_eq!(saved, r#"{"a":["j","j"]}"#);
+
+        let xplr = xplr.press_keys(&["@", "a"]).unwrap();
+        assert_eq!(focus(&xplr), 4);
+    }
+
+    #[test]
+    fn test_panel_focus() {
+        let lua = Lua::new();
+        let mut xplr = Harness::new(&lua, PWD, None).unwrap();
+        assert_eq!(
+            xplr.ui.rendered_panels,
+            vec!["Table", "InputAndLogs", "Selection", "HelpMenu"]
+        );
+
+        let mut focused = vec![];
+        for _ in 0..5 {
+            xplr = xplr.press("ctrl-t").unwrap();
+            focused.push(xplr.app.focused_panel_name().map(String::from));
+        }
+        assert_eq!(
+            focused,
+            vec![
+                Some("InputAndLogs".into()),
+                Some("Selection".into()),
+                Some("HelpMenu".into()),
+                None,
+                Some("InputAndLogs".into()),
+            ]
+        );
+
+        let xplr = xplr.press("esc").unwrap();
+        assert_eq!(xplr.app.focused_panel_name(), None);
+    }
+
+    #[test]
+    fn test_panel_scroll() {
+        let lua = Lua::new();
+        let msg = ExternalMsg::FocusPanel("HelpMenu".into());
+        let mut xplr = Harness::new(&lua, PWD, None)
+            .and_then(|h| h.send(msg))
+            .unwrap();
+        assert!(xplr.screen().contains("prev deep b"));
+
+        // Past the height of the panel.
+        for _ in 0..30 {
+            xplr = xplr.press("down").unwrap();
+        }
+
+        let panel = &xplr.ui.panels["HelpMenu"];
+        assert_eq!(panel.cursor, 30);
+        assert!(panel.offset > 0);
+        assert!(xplr.screen().contains("move to"));
+        assert!(!xplr.screen().contains("prev deep b"));
+
+        // The table isn't moved.
+        assert_eq!(xplr.app.directory_buffer.as_ref().unwrap().focus, 0);
+
+        let xplr = xplr.press("page-up").unwrap();
+        assert_eq!(xplr.ui.panels["HelpMenu"].cursor, 20);
+
+        let xplr = (0..10).try_fold(xplr, |h, _| h.press("page-down")).unwrap();
+        let panel = &xplr.ui.panels["HelpMenu"];
+        assert_eq!(panel.cursor, panel.lines.len() - 1);
+    }
+
+    #[test]
+    fn test_panel_search() {
+        let lua = Lua::new();
+        let msg = ExternalMsg::FocusPanel("HelpMenu".into());
+        let xplr = Harness::new(&lua, PWD, None)
+            .and_then(|h| h.send(msg))
+            .and_then(|h| h.press_keys(&["/", "Q", "u", "i", "t"]))
+            .unwrap();
+
+        let matches = xplr.ui.panels["HelpMenu"]
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.to_lowercase().contains("quit"))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert!(matches.len() > 1);
+        assert_eq!(xplr.ui.panels["HelpMenu"].cursor, matches[0]);
+
+        let xplr = xplr.press_keys(&["enter", "n"]).unwrap();
+        assert_eq!(xplr.app.mode.name, "default");
+        assert_eq!(xplr.ui.panels["HelpMenu"].cursor, matches[1]);
+
+        let xplr = xplr.press("N").unwrap();
+        assert_eq!(xplr.ui.panels["HelpMenu"].cursor, matches[0]);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::app::{ExternalMsg, InternalMsg, MsgIn, Task};
+use crate::lua;
+use anyhow::{bail, Result};
+use mlua::{Function, Lua, LuaSerdeExt, Table};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LUA_TIMERS: &str = "xplr_timers";
+
+/// What to do when a timer fires.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Enqueue the messages.
+    Enqueue(Vec<ExternalMsg>),
+
+    /// Call the Lua function registered with the timer's id.
+    CallLua,
+
+    /// Time out the pending key sequence with the id.
+    KeySequenceTimeout(usize),
+}
+
+enum Command {
+    Schedule {
+        id: String,
+        delay: Duration,
+        interval: Option<Duration>,
+        action: Action,
+    },
+    Cancel(String),
+}
+
+struct Entry {
+    due: Instant,
+    interval: Option<Duration>,
+    action: Action,
+}
+
+/// A handle to the timer thread, which sends the tasks into the runner's
+/// channel when the timers fire.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    tx: Sender<Command>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl Timer {
+    pub fn start(tx_msg_in: Sender<Task>) -> Self {
+        let (tx, rx) = mpsc::channel::<Command>();
+
+        thread::spawn(move || {
+            let mut timers: HashMap<String, Entry> = Default::default();
+
+            loop {
+                let next_due = timers.values().map(|e| e.due).min();
+                let cmd = match next_due {
+                    Some(due) => {
+                        match rx
+                            .recv_timeout(due.saturating_duration_since(Instant::now()))
+                        {
+                            Ok(cmd) => Some(cmd),
+                            Err(RecvTimeoutError::Timeout) => None,
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                    }
+                    None => match rx.recv() {
+                        Ok(cmd) => Some(cmd),
+                        Err(_) => break,
+                    },
+                };
+
+                match cmd {
+                    Some(Command::Schedule {
+                        id,
+                        delay,
+                        interval,
+                        action,
+                    }) => {
+                        let due = Instant::now() + delay;
+                        timers.insert(
+                            id,
+                            Entry {
+                                due,
+                                interval,
+                                action,
+                            },
+                        );
+                    }
+                    Some(Command::Cancel(id)) => {
+                        timers.remove(&id);
+                    }
+                    None => {}
+                }
+
+                let now = Instant::now();
+                let fired: Vec<String> = timers
+                    .iter()
+                    .filter(|(_, e)| e.due <= now)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+
+                for id in fired {
+                    let Some(entry) = timers.remove(&id) else {
+                        continue;
+                    };
+
+                    let msgs = match &entry.action {
+                        Action::Enqueue(msgs) => {
+                            msgs.iter().cloned().map(MsgIn::External).collect()
+                        }
+                        Action::CallLua => {
+                            vec![MsgIn::Internal(InternalMsg::CallLuaTimer(id.clone()))]
+                        }
+                        Action::KeySequenceTimeout(seq) => {
+                            vec![MsgIn::Internal(InternalMsg::KeySequenceTimeout(*seq))]
+                        }
+                    };
+
+                    for msg in msgs {
+                        // Let's not panic if xplr closes.
+                        tx_msg_in.send(Task::new(msg, None)).unwrap_or_default();
+                    }
+
+                    if let Some(interval) = entry.interval {
+                        let due = now + interval;
+                        timers.insert(id, Entry { due, ..entry });
+                    }
+                }
+            }
+        });
+
+        Self {
+            tx,
+            next_id: Default::default(),
+        }
+    }
+
+    /// Schedule the action after the delay, and then every interval if
+    /// given. An existing timer with the same id is replaced. Returns the id,
+    /// generated if not given. A zero interval is rejected, as it would keep
+    /// the timer thread busy.
+    pub fn schedule(
+        &self,
+        id: Option<String>,
+        delay: Duration,
+        interval: Option<Duration>,
+        action: Action,
+    ) -> Result<String> {
+        if interval.is_some_and(|i| i.is_zero()) {
+            bail!("the interval must be greater than 0");
+        }
+
+        let id = id.unwrap_or_else(|| {
+            format!("timer-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+        });
+
+        self.tx.send(Command::Schedule {
+            id: id.clone(),
+            delay,
+            interval,
+            action,
+        })?;
+        Ok(id)
+    }
+
+    pub fn cancel(&self, id: String) -> Result<()> {
+        self.tx.send(Command::Cancel(id))?;
+        Ok(())
+    }
+}
+
+fn lua_timers(lua: &Lua) -> mlua::Result<Table> {
+    match lua.named_registry_value::<Option<Table>>(LUA_TIMERS)? {
+        Some(t) => Ok(t),
+        None => {
+            let t = lua.create_table()?;
+            lua.set_named_registry_value(LUA_TIMERS, &t)?;
+            Ok(t)
+        }
+    }
+}
+
+/// Schedule the Lua function. Used by `xplr.util.set_timeout` and
+/// `xplr.util.set_interval`. There's no timer, and so no id, when running
+/// headless or on the async Lua worker.
+pub(crate) fn schedule_lua(
+    lua: &Lua,
+    delay_ms: u64,
+    interval: bool,
+    func: Function,
+) -> mlua::Result<Option<String>> {
+    let Some(timer) = lua.app_data_ref::<Timer>() else {
+        return Ok(None);
+    };
+
+    let delay = Duration::from_millis(delay_ms);
+    let id = timer
+        .schedule(None, delay, interval.then_some(delay), Action::CallLua)
+        .map_err(mlua::Error::external)?;
+
+    let entry = lua.create_table()?;
+    entry.set("func", func)?;
+    entry.set("once", !interval)?;
+    lua_timers(lua)?.set(id.as_str(), entry)?;
+    Ok(Some(id))
+}
+
+/// Cancel the timer and forget its Lua function, if any. Used by
+/// `xplr.util.clear_timer`.
+pub(crate) fn cancel_lua(lua: &Lua, id: String) -> mlua::Result<()> {
+    lua_timers(lua)?.set(id.as_str(), mlua::Value::Nil)?;
+    if let Some(timer) = lua.app_data_ref::<Timer>() {
+        timer.cancel(id).map_err(mlua::Error::external)?;
+    }
+    Ok(())
+}
+
+/// Call the Lua function of the fired timer, if it wasn't cancelled in the
+/// meantime.
+pub fn call_lua(
+    lua: &Lua,
+    id: &str,
+    arg: mlua::Value,
+) -> Result<Option<Vec<ExternalMsg>>> {
+    let timers = lua_timers(lua)?;
+    let Some(entry) = timers.get::<Option<Table>>(id)? else {
+        return Ok(None);
+    };
+
+    if entry.get::<bool>("once")? {
+        timers.set(id, mlua::Value::Nil)?;
+    }
+
+    let func: Function = entry.get("func")?;
+    lua::app::with_app(lua, &arg, || {
+        let res: mlua::Value = func.call(arg.clone())?;
+        Ok(lua.from_value(res)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer() {
+        let (tx, rx) = mpsc::channel();
+        let timer = Timer::start(tx);
+        let after = |ms| Duration::from_millis(ms);
+
+        let msgs = vec![ExternalMsg::Refresh];
+        timer
+            .schedule(Some("a".into()), after(50), None, Action::Enqueue(msgs))
+            .unwrap();
+        timer.cancel("a".into()).unwrap();
+
+        let msgs = vec![ExternalMsg::LogInfo("b".into())];
+        timer
+            .schedule(Some("b".into()), after(10), None, Action::Enqueue(msgs))
+            .unwrap();
+
+        let id = timer
+            .schedule(None, after(10), Some(after(10)), Action::CallLua)
+            .unwrap();
+
+        let mut b = 0;
+        let mut calls = 0;
+        let deadline = Instant::now() + after(200);
+        while let Ok(task) = rx.recv_timeout(deadline - Instant::now()) {
+            match task.msg {
+                MsgIn::External(ExternalMsg::LogInfo(s)) if s == "b" => b += 1,
+                MsgIn::Internal(InternalMsg::CallLuaTimer(i)) if i == id => calls += 1,
+                msg => panic!("unexpected message: {msg:?}"),
+            }
+            if calls == 3 {
+                break;
+            }
+        }
+
+        assert_eq!(b, 1);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_zero_interval() {
+        let (tx, rx) = mpsc::channel();
+        let timer = Timer::start(tx);
+
+        let res =
+            timer.schedule(None, Duration::ZERO, Some(Duration::ZERO), Action::CallLua);
+        assert!(res.is_err());
+
+        timer
+            .schedule(None, Duration::ZERO, None, Action::KeySequenceTimeout(7))
+            .unwrap();
+        let task = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(matches!(
+            task.msg,
+            MsgIn::Internal(InternalMsg::KeySequenceTimeout(7))
+        ));
+    }
+}
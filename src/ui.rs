@@ -1,9 +1,15 @@
-use crate::app::{HelpMenuLine, NodeFilterApplicable, NodeSorterApplicable};
+use crate::app::{
+    ExternalMsg, HelpMenuLine, NodeFilterApplicable, NodeSorterApplicable,
+};
 use crate::app::{Node, ResolvedNode};
 use crate::compat::{draw_custom_content, CustomContent};
+use crate::config::LogsConfig;
 use crate::config::PanelUiConfig;
+use crate::hash::{ChecksumStatus, HashAlgorithm, NodeHashes};
 use crate::lua;
 use crate::permissions::Permissions;
+use crate::users;
+use crate::xattr::ExtendedMetadata;
 use crate::{app, path};
 use ansi_to_tui::IntoText;
 use indexmap::IndexSet;
@@ -12,17 +18,20 @@ use lscolors::{Color as LsColorsColor, Style as LsColorsStyle};
 use mlua::Lua;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::ops::BitXor;
+use time::format_description::BorrowedFormatItem;
 use time::macros::format_description;
 use tui::layout::Rect as TuiRect;
-use tui::layout::{Constraint as TuiConstraint, Direction, Layout as TuiLayout};
+use tui::layout::{
+    Constraint as TuiConstraint, Direction, Layout as TuiLayout, Position,
+};
 use tui::style::{Color as TuiColor, Modifier as TuiModifier, Style as TuiStyle};
 use tui::text::{Line, Span, Text};
 use tui::widgets::{
     Block, BorderType as TuiBorderType, Borders as TuiBorders, Cell, List, ListItem,
-    Paragraph, Row, Table,
+    ListState, Paragraph, Row, Table, TableState,
 };
 use tui::Frame;
 
@@ -40,6 +49,24 @@ fn read_only_indicator(app: &app::App) -> &str {
     }
 }
 
+fn macro_recording_indicator(app: &app::App) -> String {
+    app.macro_recording
+        .as_ref()
+        .map(|rec| format!("(@{})", rec.register))
+        .unwrap_or_default()
+}
+
+fn pending_keys_indicator(app: &app::App) -> String {
+    let count = app.count.map(|c| c.to_string());
+    let keys = app.pending_keys.iter().map(|k| k.to_string());
+    let pending = count.into_iter().chain(keys).collect::<Vec<String>>();
+    if pending.is_empty() {
+        String::new()
+    } else {
+        format!(" {}…", pending.join(" "))
+    }
+}
+
 fn selection_indicator(app: &app::App) -> String {
     let count = app.selection.len();
     if count == 0 {
@@ -124,18 +151,70 @@ pub enum CustomPanel {
     CustomList {
         #[serde(default)]
         ui: PanelUiConfig,
-        body: Vec<String>,
+        body: Vec<CustomListItem>,
+
+        #[serde(default)]
+        name: Option<String>,
     },
     CustomTable {
         #[serde(default)]
         ui: PanelUiConfig,
         widths: Vec<Constraint>,
         col_spacing: Option<u16>,
-        body: Vec<Vec<String>>,
+        body: Vec<CustomTableRow>,
+
+        #[serde(default)]
+        name: Option<String>,
     },
     CustomLayout(Layout),
 }
 
+/// An item of a custom list. It can optionally carry the messages to send when
+/// it's clicked, or activated via the keyboard.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum CustomListItem {
+    Text(String),
+    Action {
+        text: String,
+
+        #[serde(default)]
+        messages: Vec<ExternalMsg>,
+    },
+}
+
+impl CustomListItem {
+    fn into_parts(self) -> (String, Vec<ExternalMsg>) {
+        match self {
+            Self::Text(text) => (text, vec![]),
+            Self::Action { text, messages } => (text, messages),
+        }
+    }
+}
+
+/// A row of a custom table. Like [`CustomListItem`], it can optionally carry
+/// the messages to send when it's activated.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum CustomTableRow {
+    Cols(Vec<String>),
+    Action {
+        cols: Vec<String>,
+
+        #[serde(default)]
+        messages: Vec<ExternalMsg>,
+    },
+}
+
+impl CustomTableRow {
+    fn into_parts(self) -> (Vec<String>, Vec<ExternalMsg>) {
+        match self {
+            Self::Cols(cols) => (cols, vec![]),
+            Self::Action { cols, messages } => (cols, messages),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 #[derive(Default)]
@@ -147,6 +226,8 @@ pub enum Layout {
     Selection,
     HelpMenu,
     SortAndFilter,
+    Logs,
+    CommandPalette,
     Static(Box<CustomPanel>),
     Dynamic(String),
     Horizontal {
@@ -645,8 +726,11 @@ pub struct NodeUiMetadata {
     pub last_modified: Option<u128>,
     pub uid: u32,
     pub gid: u32,
+    pub extended_metadata: Option<ExtendedMetadata>,
 
     // Extra
+    pub user_name: Option<String>,
+    pub group_name: Option<String>,
     pub index: usize,
     pub relative_index: usize,
     pub is_before_focus: bool,
@@ -659,6 +743,8 @@ pub struct NodeUiMetadata {
     pub total: usize,
     pub meta: HashMap<String, String>,
     pub style: Style,
+    pub hashes: BTreeMap<HashAlgorithm, String>,
+    pub checksum: Option<ChecksumStatus>,
 }
 
 impl NodeUiMetadata {
@@ -676,7 +762,11 @@ impl NodeUiMetadata {
         total: usize,
         meta: HashMap<String, String>,
         style: Style,
+        hashes: Option<NodeHashes>,
     ) -> Self {
+        let (hashes, checksum) =
+            hashes.map(|h| (h.digests, h.checksum)).unwrap_or_default();
+
         Self {
             parent: node.parent.clone(),
             relative_path: node.relative_path.clone(),
@@ -697,6 +787,9 @@ impl NodeUiMetadata {
             last_modified: node.last_modified,
             uid: node.uid,
             gid: node.gid,
+            extended_metadata: node.extended_metadata.clone(),
+            user_name: users::user_name(node.uid),
+            group_name: users::group_name(node.gid),
             index,
             relative_index,
             is_before_focus,
@@ -709,6 +802,8 @@ impl NodeUiMetadata {
             total,
             meta,
             style,
+            hashes,
+            checksum,
         }
     }
 }
@@ -737,6 +832,108 @@ pub struct UI<'lua> {
     pub lua: &'lua Lua,
     pub screen_size: TuiRect,
     pub scrolltop: usize,
+
+    /// The area where the table rows were last rendered, if the table is
+    /// visible.
+    pub table_rows: Option<TuiRect>,
+
+    /// The custom panel rows with actions, rendered last time.
+    pub clickable_rows: Vec<(TuiRect, Vec<ExternalMsg>)>,
+
+    /// The names of the focusable panels, in the order they were rendered last
+    /// time.
+    pub rendered_panels: Vec<String>,
+
+    /// The state of the focusable panels.
+    pub panels: HashMap<String, PanelState>,
+
+    /// The last string searched in the panels.
+    pub panel_search: Option<String>,
+}
+
+/// The state of a focusable panel.
+#[derive(Debug, Clone, Default)]
+pub struct PanelState {
+    /// The text of the rows, rendered last time, used for searching.
+    pub lines: Vec<String>,
+
+    /// The messages of the rows, if any.
+    pub actions: Vec<Vec<ExternalMsg>>,
+
+    /// The row under the keyboard focus.
+    pub cursor: usize,
+
+    /// The scroll offset.
+    pub offset: usize,
+}
+
+impl PanelState {
+    /// Find the row that contains the query, starting from the given row, and
+    /// wrapping around.
+    fn find(&self, query: &str, from: usize, reverse: bool) -> Option<usize> {
+        let len = self.lines.len();
+        let query = query.to_lowercase();
+        (0..len)
+            .map(|i| {
+                if reverse {
+                    (from + len - i) % len
+                } else {
+                    (from + i) % len
+                }
+            })
+            .find(|i| self.lines[*i].to_lowercase().contains(&query))
+    }
+}
+
+fn log_item<'a>(
+    log: &app::Log,
+    config: &LogsConfig,
+    time_format: &[BorrowedFormatItem],
+    max_lines: usize,
+) -> ListItem<'a> {
+    let time = log
+        .created_at
+        .format(time_format)
+        .unwrap_or_else(|_| "when?".into());
+    let cfg = match log.level {
+        app::LogLevel::Info => &config.info,
+        app::LogLevel::Warning => &config.warning,
+        app::LogLevel::Success => &config.success,
+        app::LogLevel::Error => &config.error,
+    };
+
+    let prefix = format!("{time}|{0}", cfg.format.clone().unwrap_or_default());
+
+    let padding = " ".repeat(prefix.chars().count());
+
+    let txt = log
+        .message
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                format!("{prefix}) {line}")
+            } else {
+                format!("{padding}  {line}")
+            }
+        })
+        .take(max_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ListItem::new(txt).style(cfg.style.clone())
+}
+
+fn is_focused(app: &app::App, name: &str) -> bool {
+    app.focused_panel.as_ref().is_some_and(|p| p.name == name)
+}
+
+fn focused_config(app: &app::App, name: &str, config: PanelUiConfig) -> PanelUiConfig {
+    if is_focused(app, name) {
+        config.extend(&app.config.general.panel_ui.focused)
+    } else {
+        config
+    }
 }
 
 impl<'lua> UI<'lua> {
@@ -747,6 +944,187 @@ impl<'lua> UI<'lua> {
             lua,
             scrolltop,
             screen_size,
+            table_rows: None,
+            clickable_rows: Default::default(),
+            rendered_panels: Default::default(),
+            panels: Default::default(),
+            panel_search: None,
+        }
+    }
+
+    /// The messages of the custom panel row rendered at the screen position.
+    pub fn row_action_at(&self, column: u16, row: u16) -> Option<Vec<ExternalMsg>> {
+        self.clickable_rows
+            .iter()
+            .find(|(area, _)| area.contains(Position::new(column, row)))
+            .map(|(_, msgs)| msgs.clone())
+    }
+
+    /// The panel rendered after the focused one, or the first one.
+    pub fn next_panel(&self, focused: Option<&str>) -> Option<String> {
+        let focused = focused.unwrap_or(app::TABLE_PANEL);
+        let next = self
+            .rendered_panels
+            .iter()
+            .position(|p| p == focused)
+            .map(|i| i + 1)
+            .unwrap_or_default();
+
+        self.rendered_panels
+            .get(next)
+            .or_else(|| self.rendered_panels.first())
+            .cloned()
+    }
+
+    /// Move the cursor of the focused panel by the given offset.
+    pub fn move_panel_cursor(&mut self, focused: Option<&str>, offset: isize) {
+        if let Some(panel) = focused.and_then(|n| self.panels.get_mut(n)) {
+            let last = panel.lines.len().saturating_sub(1);
+            panel.cursor = panel.cursor.saturating_add_signed(offset).min(last);
+        }
+    }
+
+    /// The messages of the focused panel row under the cursor.
+    pub fn focused_panel_action(
+        &self,
+        focused: Option<&str>,
+    ) -> Option<Vec<ExternalMsg>> {
+        let panel = self.panels.get(focused?)?;
+        panel
+            .actions
+            .get(panel.cursor)
+            .filter(|m| !m.is_empty())
+            .cloned()
+    }
+
+    /// Move the cursor of the focused panel to the row matching the query.
+    pub fn search_panel(&mut self, focused: Option<&str>, query: String) {
+        if let Some(panel) = focused.and_then(|n| self.panels.get_mut(n)) {
+            if let Some(i) = panel.find(&query, panel.cursor, false) {
+                panel.cursor = i;
+            }
+        }
+        self.panel_search = Some(query);
+    }
+
+    /// Move the cursor of the focused panel to the next or previous row
+    /// matching the last search.
+    pub fn search_panel_next(&mut self, focused: Option<&str>, reverse: bool) {
+        let Some(query) = self.panel_search.as_ref() else {
+            return;
+        };
+
+        if let Some(panel) = focused.and_then(|n| self.panels.get_mut(n)) {
+            let len = panel.lines.len().max(1);
+            let from = if reverse {
+                (panel.cursor + len - 1) % len
+            } else {
+                (panel.cursor + 1) % len
+            };
+
+            if let Some(i) = panel.find(query, from, reverse) {
+                panel.cursor = i;
+            }
+        }
+    }
+
+    /// Remember the rendered rows of a focusable panel, and return the cursor
+    /// and the scroll offset to render it with, if the panel is focused.
+    fn sync_panel(
+        &mut self,
+        app: &app::App,
+        name: &str,
+        lines: Vec<String>,
+        actions: Vec<Vec<ExternalMsg>>,
+    ) -> Option<(usize, usize)> {
+        self.rendered_panels.push(name.into());
+        if !is_focused(app, name) {
+            return None;
+        }
+
+        let panel = self.panels.entry(name.into()).or_default();
+        panel.cursor = panel.cursor.min(lines.len().saturating_sub(1));
+        panel.lines = lines;
+        panel.actions = actions;
+
+        if panel.lines.is_empty() {
+            None
+        } else {
+            Some((panel.cursor, panel.offset))
+        }
+    }
+
+    fn save_panel_offset(&mut self, app: &app::App, name: &str, offset: usize) {
+        if !is_focused(app, name) {
+            return;
+        }
+
+        if let Some(panel) = self.panels.get_mut(name) {
+            panel.offset = offset;
+        }
+    }
+
+    fn panel_list_state(
+        &mut self,
+        app: &app::App,
+        name: &str,
+        lines: Vec<String>,
+        actions: Vec<Vec<ExternalMsg>>,
+    ) -> ListState {
+        self.sync_panel(app, name, lines, actions)
+            .map(|(cursor, offset)| {
+                ListState::default()
+                    .with_offset(offset)
+                    .with_selected(Some(cursor))
+            })
+            .unwrap_or_default()
+    }
+
+    fn panel_table_state(
+        &mut self,
+        app: &app::App,
+        name: &str,
+        lines: Vec<String>,
+        actions: Vec<Vec<ExternalMsg>>,
+    ) -> TableState {
+        self.sync_panel(app, name, lines, actions)
+            .map(|(cursor, offset)| {
+                TableState::default()
+                    .with_offset(offset)
+                    .with_selected(Some(cursor))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remember where the rows with actions were rendered, for mouse clicks.
+    fn register_clickable_rows(
+        &mut self,
+        area: TuiRect,
+        offset: usize,
+        rows: Vec<(u16, Vec<ExternalMsg>)>,
+    ) {
+        let mut y = area.y;
+        for (height, msgs) in rows.into_iter().skip(offset) {
+            if y >= area.bottom() {
+                break;
+            }
+
+            let height = height.min(area.bottom() - y);
+            if !msgs.is_empty() {
+                let rect = TuiRect { y, height, ..area };
+                self.clickable_rows.push((rect, msgs));
+            }
+            y += height;
+        }
+    }
+
+    /// Resolve the screen position to the index of the node rendered there.
+    pub fn node_index_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.table_rows?;
+        if area.contains(Position::new(column, row)) {
+            Some(self.scrolltop + usize::from(row - area.y))
+        } else {
+            None
         }
     }
 }
@@ -864,6 +1242,10 @@ impl UI<'_> {
                             dir.total,
                             node_type.meta,
                             style,
+                            app.hashes
+                                .get(&node.absolute_path)
+                                .filter(|h| h.is_fresh(node))
+                                .cloned(),
                         );
 
                         let cols = lua::serialize::<NodeUiMetadata>(self.lua, &meta)
@@ -924,14 +1306,22 @@ impl UI<'_> {
             format!("({node_count}) ")
         };
 
+        let table_block =
+            block(config, format!(" {vroot_indicator}/{pwd} {node_count}"));
+
+        let inner = table_block.inner(layout_size);
+        self.rendered_panels.push(app::TABLE_PANEL.into());
+        self.table_rows = Some(TuiRect {
+            y: inner.y.saturating_add(header_height),
+            height: inner.height.saturating_sub(header_height),
+            ..inner
+        });
+
         let table = Table::new(rows, table_constraints)
             .style(app_config.general.table.style.clone())
             .row_highlight_style(app_config.general.focus_ui.style.clone())
             .column_spacing(app_config.general.table.col_spacing.unwrap_or_default())
-            .block(block(
-                config,
-                format!(" {vroot_indicator}/{pwd} {node_count}"),
-            ));
+            .block(table_block);
 
         let table = table.clone().header(
             Row::new(
@@ -957,17 +1347,28 @@ impl UI<'_> {
     }
 
     fn draw_selection(&mut self, f: &mut Frame, layout_size: TuiRect, app: &app::App) {
+        let name = "Selection";
         let panel_config = &app.config.general.panel_ui;
         let config = panel_config.default.clone().extend(&panel_config.selection);
+        let config = focused_config(app, name, config);
 
         let selection_count = app.selection.len();
 
-        let selection: Vec<ListItem> = app
-            .selection
-            .iter()
-            .rev()
-            .take((layout_size.height.max(2) - 2).into())
-            .rev()
+        // Show the last selected nodes, unless the panel is focused for scrolling.
+        let nodes = if is_focused(app, name) {
+            app.selection.iter().collect::<Vec<&Node>>()
+        } else {
+            let height = (layout_size.height.max(2) - 2).into();
+            let mut nodes = app.selection.iter().rev().take(height).collect::<Vec<_>>();
+            nodes.reverse();
+            nodes
+        };
+
+        let lines = nodes.iter().map(|n| n.absolute_path.clone()).collect();
+        let mut state = self.panel_list_state(app, name, lines, vec![]);
+
+        let selection: Vec<ListItem> = nodes
+            .into_iter()
             .map(|n| {
                 let out = app
                     .config
@@ -997,19 +1398,34 @@ impl UI<'_> {
         };
 
         let selection_list = List::new(selection)
+            .highlight_style(app.config.general.focus_ui.style.clone())
             .block(block(config, format!(" Selection {selection_count}")));
 
-        f.render_widget(selection_list, layout_size);
+        f.render_stateful_widget(selection_list, layout_size, &mut state);
+        self.save_panel_offset(app, name, state.offset());
     }
 
     fn draw_help_menu(&mut self, f: &mut Frame, layout_size: TuiRect, app: &app::App) {
+        let name = "HelpMenu";
         let panel_config = &app.config.general.panel_ui;
 
         let config = panel_config.default.clone().extend(&panel_config.help_menu);
+        let config = focused_config(app, name, config);
 
-        let help_menu_rows = app
-            .mode
-            .help_menu()
+        let mode = app.help_menu_mode();
+        let help_menu = mode.help_menu();
+        let lines = help_menu
+            .iter()
+            .map(|l| match l {
+                HelpMenuLine::Paragraph(p) => p.clone(),
+                HelpMenuLine::KeyMap(k, remaps, h) => {
+                    format!("{k} {} {h}", remaps.join("|"))
+                }
+            })
+            .collect();
+        let mut state = self.panel_table_state(app, name, lines, vec![]);
+
+        let help_menu_rows = help_menu
             .into_iter()
             .map(|l| match l {
                 HelpMenuLine::Paragraph(p) => Row::new([Cell::from(p)].to_vec()),
@@ -1033,11 +1449,20 @@ impl UI<'_> {
                 TuiConstraint::Percentage(60),
             ]
         };
-        let help_menu = Table::new(help_menu_rows, widths).block(block(
-            config,
-            format!(" Help [{}{}] ", &app.mode.name, read_only_indicator(app)),
-        ));
-        f.render_widget(help_menu, layout_size);
+        let help_menu = Table::new(help_menu_rows, widths)
+            .row_highlight_style(app.config.general.focus_ui.style.clone())
+            .block(block(
+                config,
+                format!(
+                    " Help [{}{}{}{}] ",
+                    &mode.name,
+                    pending_keys_indicator(app),
+                    read_only_indicator(app),
+                    macro_recording_indicator(app),
+                ),
+            ));
+        f.render_stateful_widget(help_menu, layout_size, &mut state);
+        self.save_panel_offset(app, name, state.offset());
     }
 
     fn draw_input_buffer(
@@ -1082,9 +1507,11 @@ impl UI<'_> {
             .block(block(
                 config,
                 format!(
-                    " Input [{}{}]{} ",
+                    " Input [{}{}{}{}]{} ",
                     app.mode.name,
+                    pending_keys_indicator(app),
                     read_only_indicator(app),
+                    macro_recording_indicator(app),
                     selection_indicator(app),
                 ),
             ));
@@ -1225,51 +1652,44 @@ impl UI<'_> {
     }
 
     fn draw_logs(&mut self, f: &mut Frame, layout_size: TuiRect, app: &app::App) {
+        let name = "InputAndLogs";
         let panel_config = &app.config.general.panel_ui;
         let config = panel_config
             .default
             .clone()
             .extend(&panel_config.input_and_logs);
+        let config = focused_config(app, name, config);
         let logs_config = app.config.general.logs.clone();
+
+        // Show all the logs only when the panel is focused for scrolling.
+        let count = if is_focused(app, name) {
+            app.logs.len()
+        } else {
+            layout_size.height as usize
+        };
+
+        let lines = if app.logs_hidden {
+            vec![]
+        } else {
+            app.logs
+                .iter()
+                .rev()
+                .take(count)
+                .map(|l| l.message.clone())
+                .collect()
+        };
+        let mut state = self.panel_list_state(app, name, lines, vec![]);
+
         let logs = if app.logs_hidden {
             vec![]
         } else {
             app.logs
                 .iter()
                 .rev()
-                .take(layout_size.height as usize)
+                .take(count)
                 .map(|log| {
                     let fd = format_description!("[hour]:[minute]:[second]");
-                    let time =
-                        log.created_at.format(fd).unwrap_or_else(|_| "when?".into());
-                    let cfg = match log.level {
-                        app::LogLevel::Info => &logs_config.info,
-                        app::LogLevel::Warning => &logs_config.warning,
-                        app::LogLevel::Success => &logs_config.success,
-                        app::LogLevel::Error => &logs_config.error,
-                    };
-
-                    let prefix =
-                        format!("{time}|{0}", cfg.format.clone().unwrap_or_default());
-
-                    let padding = " ".repeat(prefix.chars().count());
-
-                    let txt = log
-                        .message
-                        .lines()
-                        .enumerate()
-                        .map(|(i, line)| {
-                            if i == 0 {
-                                format!("{prefix}) {line}")
-                            } else {
-                                format!("{padding}  {line}")
-                            }
-                        })
-                        .take(layout_size.height as usize)
-                        .collect::<Vec<_>>()
-                        .join("\n");
-
-                    ListItem::new(txt).style(cfg.style.clone())
+                    log_item(log, &logs_config, fd, layout_size.height.into())
                 })
                 .collect::<Vec<ListItem>>()
         };
@@ -1281,18 +1701,110 @@ impl UI<'_> {
             format!(" ({logs_count})")
         };
 
-        let logs_list = List::new(logs).block(block(
-            config,
-            format!(
-                " Logs{} [{}{}]{} ",
-                logs_count,
-                app.mode.name,
-                read_only_indicator(app),
-                selection_indicator(app)
-            ),
-        ));
-
-        f.render_widget(logs_list, layout_size);
+        let logs_list = List::new(logs)
+            .highlight_style(app.config.general.focus_ui.style.clone())
+            .block(block(
+                config,
+                format!(
+                    " Logs{} [{}{}{}{}]{} ",
+                    logs_count,
+                    app.mode.name,
+                    pending_keys_indicator(app),
+                    read_only_indicator(app),
+                    macro_recording_indicator(app),
+                    selection_indicator(app)
+                ),
+            ));
+
+        f.render_stateful_widget(logs_list, layout_size, &mut state);
+        self.save_panel_offset(app, name, state.offset());
+    }
+
+    fn draw_log_viewer(&mut self, f: &mut Frame, layout_size: TuiRect, app: &app::App) {
+        let name = "Logs";
+        let panel_config = &app.config.general.panel_ui;
+        let config = panel_config
+            .default
+            .clone()
+            .extend(&panel_config.input_and_logs);
+        let config = focused_config(app, name, config);
+        let logs_config = &app.config.general.logs;
+
+        let logs = app.filtered_logs().rev().collect::<Vec<_>>();
+
+        let lines = logs.iter().map(|(_, l)| l.message.clone()).collect();
+        let actions = logs
+            .iter()
+            .map(|(i, _)| vec![ExternalMsg::CopyLog(*i)])
+            .collect();
+        let mut state = self.panel_list_state(app, name, lines, actions);
+
+        let fd = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+        let items = logs
+            .iter()
+            .map(|(_, log)| log_item(log, logs_config, fd, usize::MAX))
+            .collect::<Vec<ListItem>>();
+
+        let filter = app
+            .log_level_filter
+            .map(|l| format!(" [{l}]"))
+            .unwrap_or_default();
+
+        let logs_list = List::new(items)
+            .highlight_style(app.config.general.focus_ui.style.clone())
+            .block(block(
+                config,
+                format!(" Logs ({0}/{1}){filter} ", logs.len(), app.logs.len()),
+            ));
+
+        f.render_stateful_widget(logs_list, layout_size, &mut state);
+        self.save_panel_offset(app, name, state.offset());
+    }
+
+    fn draw_command_palette(
+        &mut self,
+        f: &mut Frame,
+        layout_size: TuiRect,
+        app: &app::App,
+    ) {
+        let config = app.config.general.panel_ui.default.clone();
+        let Some(palette) = app.command_palette.as_ref() else {
+            let content =
+                Paragraph::new("").block(block(config, " Command Palette ".into()));
+            f.render_widget(content, layout_size);
+            return;
+        };
+
+        let rows = palette
+            .matched_items()
+            .map(|i| {
+                Row::new([
+                    Cell::from(i.help.clone()),
+                    Cell::from(i.keys.join("|")),
+                    Cell::from(i.mode.clone()),
+                ])
+            })
+            .collect::<Vec<Row>>();
+
+        let widths = vec![
+            TuiConstraint::Percentage(50),
+            TuiConstraint::Percentage(25),
+            TuiConstraint::Percentage(25),
+        ];
+
+        let mut state = TableState::default().with_selected(Some(palette.focus));
+        let table = Table::new(rows, widths)
+            .row_highlight_style(app.config.general.focus_ui.style.clone())
+            .block(block(
+                config,
+                format!(
+                    " Command Palette ({0}/{1}) ",
+                    palette.matches.len(),
+                    palette.items.len()
+                ),
+            ));
+
+        f.render_stateful_widget(table, layout_size, &mut state);
     }
 
     fn draw_nothing(&mut self, f: &mut Frame, layout_size: TuiRect, app: &app::App) {
@@ -1342,17 +1854,44 @@ impl UI<'_> {
                 f.render_widget(content, layout_size);
             }
 
-            CustomPanel::CustomList { ui, body } => {
-                let config = defaultui.extend(&ui);
+            CustomPanel::CustomList { ui, body, name } => {
+                let mut config = defaultui.extend(&ui);
+                if let Some(name) = name.as_ref() {
+                    config = focused_config(app, name, config);
+                }
+                let panel_block = block(config, "".into());
+                let inner = panel_block.inner(layout_size);
 
-                let items = body
-                    .into_iter()
+                let (lines, actions): (Vec<String>, Vec<Vec<ExternalMsg>>) =
+                    body.into_iter().map(CustomListItem::into_parts).unzip();
+
+                let texts = lines
+                    .iter()
+                    .cloned()
                     .map(string_to_text)
+                    .collect::<Vec<Text>>();
+
+                let heights = texts.iter().map(|t| t.height() as u16);
+                let clickable = heights.zip(actions.clone()).collect();
+
+                let mut state = name
+                    .as_ref()
+                    .map(|n| self.panel_list_state(app, n, lines, actions))
+                    .unwrap_or_default();
+
+                let items = texts
+                    .into_iter()
                     .map(ListItem::new)
                     .collect::<Vec<ListItem>>();
+                let content = List::new(items)
+                    .highlight_style(app.config.general.focus_ui.style.clone())
+                    .block(panel_block);
 
-                let content = List::new(items).block(block(config, "".into()));
-                f.render_widget(content, layout_size);
+                f.render_stateful_widget(content, layout_size, &mut state);
+                if let Some(name) = name.as_ref() {
+                    self.save_panel_offset(app, name, state.offset());
+                }
+                self.register_clickable_rows(inner, state.offset(), clickable);
             }
 
             CustomPanel::CustomTable {
@@ -1360,9 +1899,20 @@ impl UI<'_> {
                 widths,
                 col_spacing,
                 body,
+                name,
             } => {
-                let config = defaultui.extend(&ui);
-                let rows = body
+                let mut config = defaultui.extend(&ui);
+                if let Some(name) = name.as_ref() {
+                    config = focused_config(app, name, config);
+                }
+                let panel_block = block(config, "".into());
+                let inner = panel_block.inner(layout_size);
+
+                let (cols, actions): (Vec<Vec<String>>, Vec<Vec<ExternalMsg>>) =
+                    body.into_iter().map(CustomTableRow::into_parts).unzip();
+
+                let lines = cols.iter().map(|c| c.join(" ")).collect::<Vec<String>>();
+                let rows = cols
                     .into_iter()
                     .map(|cols| {
                         Row::new(
@@ -1374,16 +1924,28 @@ impl UI<'_> {
                     })
                     .collect::<Vec<Row>>();
 
+                let clickable = actions.iter().map(|a| (1, a.clone())).collect();
+
                 let widths = widths
                     .into_iter()
                     .map(|w| w.to_tui(self.screen_size, layout_size))
                     .collect::<Vec<TuiConstraint>>();
 
+                let mut state = name
+                    .as_ref()
+                    .map(|n| self.panel_table_state(app, n, lines, actions))
+                    .unwrap_or_default();
+
                 let content = Table::new(rows, widths)
                     .column_spacing(col_spacing.unwrap_or(1))
-                    .block(block(config, "".into()));
+                    .row_highlight_style(app.config.general.focus_ui.style.clone())
+                    .block(panel_block);
 
-                f.render_widget(content, layout_size);
+                f.render_stateful_widget(content, layout_size, &mut state);
+                if let Some(name) = name.as_ref() {
+                    self.save_panel_offset(app, name, state.offset());
+                }
+                self.register_clickable_rows(inner, state.offset(), clickable);
             }
 
             CustomPanel::CustomLayout(layout) => {
@@ -1403,6 +1965,8 @@ impl UI<'_> {
             Layout::Nothing => self.draw_nothing(f, layout_size, app),
             Layout::Table => self.draw_table(f, layout_size, app),
             Layout::SortAndFilter => self.draw_sort_n_filter(f, layout_size, app),
+            Layout::Logs => self.draw_log_viewer(f, layout_size, app),
+            Layout::CommandPalette => self.draw_command_palette(f, layout_size, app),
             Layout::HelpMenu => self.draw_help_menu(f, layout_size, app),
             Layout::Selection => self.draw_selection(f, layout_size, app),
             Layout::InputAndLogs => {
@@ -1479,6 +2043,9 @@ impl UI<'_> {
 
     pub fn draw(&mut self, f: &mut Frame, app: &app::App) {
         self.screen_size = f.area();
+        self.table_rows = None;
+        self.clickable_rows.clear();
+        self.rendered_panels.clear();
         let layout = app.mode.layout.as_ref().unwrap_or(&app.layout).clone();
         self.draw_layout(layout, f, self.screen_size, app);
     }
@@ -1494,6 +2061,90 @@ mod tests {
         Some(x)
     }
 
+    #[test]
+    fn test_custom_panel_actions() {
+        let panel: CustomPanel = serde_json::from_str(
+            r#"{
+              "CustomList": {
+                "name": "menu",
+                "body": ["title", { "text": "quit", "messages": ["Quit"] }]
+              }
+            }"#,
+        )
+        .unwrap();
+
+        let CustomPanel::CustomList { body, name, .. } = panel else {
+            panic!("not a list");
+        };
+        assert_eq!(name.as_deref(), Some("menu"));
+
+        let actions = body
+            .into_iter()
+            .map(|i| i.into_parts().1)
+            .collect::<Vec<Vec<ExternalMsg>>>();
+        assert_eq!(actions, vec![vec![], vec![ExternalMsg::Quit]]);
+
+        let lua = Lua::new();
+        let mut ui = UI::new(&lua);
+        ui.panels.insert(
+            "menu".into(),
+            PanelState {
+                lines: vec!["title".into(), "quit".into()],
+                actions,
+                ..Default::default()
+            },
+        );
+        assert_eq!(ui.focused_panel_action(None), None);
+        assert_eq!(ui.focused_panel_action(Some("menu")), None);
+
+        ui.move_panel_cursor(Some("menu"), 5);
+        assert_eq!(
+            ui.focused_panel_action(Some("menu")),
+            Some(vec![ExternalMsg::Quit])
+        );
+
+        ui.search_panel(Some("menu"), "TIT".into());
+        assert_eq!(ui.panels["menu"].cursor, 0);
+        ui.search_panel_next(Some("menu"), true);
+        assert_eq!(ui.panels["menu"].cursor, 0);
+        ui.search_panel(Some("menu"), "i".into());
+        ui.search_panel_next(Some("menu"), false);
+        assert_eq!(ui.panels["menu"].cursor, 1);
+
+        ui.rendered_panels = vec![app::TABLE_PANEL.into(), "menu".into()];
+        assert_eq!(ui.next_panel(None), Some("menu".into()));
+        assert_eq!(ui.next_panel(Some("menu")), Some(app::TABLE_PANEL.into()));
+
+        ui.register_clickable_rows(
+            TuiRect::new(0, 1, 10, 1),
+            0,
+            vec![(1, vec![]), (1, vec![ExternalMsg::Quit])],
+        );
+        assert_eq!(ui.row_action_at(0, 1), None);
+        assert_eq!(ui.row_action_at(0, 2), None);
+
+        ui.register_clickable_rows(
+            TuiRect::new(0, 1, 10, 5),
+            1,
+            vec![(2, vec![]), (1, vec![ExternalMsg::Quit])],
+        );
+        assert_eq!(ui.row_action_at(3, 1), Some(vec![ExternalMsg::Quit]));
+    }
+
+    #[test]
+    fn test_node_index_at() {
+        let lua = Lua::new();
+        let mut ui = UI::new(&lua);
+        assert_eq!(ui.node_index_at(1, 1), None);
+
+        ui.scrolltop = 10;
+        ui.table_rows = Some(TuiRect::new(1, 2, 20, 5));
+        assert_eq!(ui.node_index_at(1, 2), Some(10));
+        assert_eq!(ui.node_index_at(20, 6), Some(14));
+        assert_eq!(ui.node_index_at(1, 7), None);
+        assert_eq!(ui.node_index_at(0, 2), None);
+    }
+
     #[test]
     fn test_extend_style() {
         let a = Style {
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs;
+use std::sync::{OnceLock, RwLock};
+
+type Cache = RwLock<HashMap<u32, Option<String>>>;
+
+static USERS: OnceLock<Cache> = OnceLock::new();
+static GROUPS: OnceLock<Cache> = OnceLock::new();
+
+/// Parse `/etc/passwd` or `/etc/group` style content into an ID to name map.
+/// Both have the name in the first field, and the ID in the third field.
+fn parse_db(content: &str) -> HashMap<u32, Option<String>> {
+    let mut map = HashMap::new();
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(id)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        if let Ok(id) = id.parse() {
+            // The first entry wins, like with the libc functions.
+            map.entry(id).or_insert_with(|| Some(name.to_string()));
+        }
+    }
+    map
+}
+
+fn load(path: &str) -> Cache {
+    RwLock::new(
+        fs::read_to_string(path)
+            .map(|c| parse_db(&c))
+            .unwrap_or_default(),
+    )
+}
+
+/// Resolve the ID using the cache, falling back to NSS via `lookup`.
+fn resolve(cache: &Cache, id: u32, lookup: fn(u32) -> Option<String>) -> Option<String> {
+    if let Some(name) = cache.read().ok().and_then(|c| c.get(&id).cloned()) {
+        return name;
+    }
+
+    let name = lookup(id);
+    if let Ok(mut c) = cache.write() {
+        c.insert(id, name.clone());
+    }
+    name
+}
+
+/// Call one of the reentrant lookup functions with a growing buffer.
+fn lookup_with_buffer<T>(
+    call: impl Fn(*mut T, *mut libc::c_char, usize, *mut *mut T) -> i32,
+    name: impl Fn(&T) -> *const libc::c_char,
+) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut entry: T = unsafe { std::mem::zeroed() };
+        let mut result: *mut T = std::ptr::null_mut();
+        let err = call(&mut entry, buf.as_mut_ptr(), buf.len(), &mut result);
+
+        if err == libc::ERANGE && buf.len() < 1024 * 1024 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+
+        if err != 0 || result.is_null() {
+            return None;
+        }
+
+        let name = unsafe { CStr::from_ptr(name(&entry)) };
+        return Some(name.to_string_lossy().to_string());
+    }
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    lookup_with_buffer(
+        |pwd, buf, len, result| unsafe { libc::getpwuid_r(uid, pwd, buf, len, result) },
+        |pwd: &libc::passwd| pwd.pw_name,
+    )
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    lookup_with_buffer(
+        |grp, buf, len, result| unsafe { libc::getgrgid_r(gid, grp, buf, len, result) },
+        |grp: &libc::group| grp.gr_name,
+    )
+}
+
+/// Get the name of the user with the given ID. The results are cached for the
+/// lifetime of the process.
+pub fn user_name(uid: u32) -> Option<String> {
+    let cache = USERS.get_or_init(|| load("/etc/passwd"));
+    resolve(cache, uid, lookup_user)
+}
+
+/// Get the name of the group with the given ID. The results are cached for
+/// the lifetime of the process.
+pub fn group_name(gid: u32) -> Option<String> {
+    let cache = GROUPS.get_or_init(|| load("/etc/group"));
+    resolve(cache, gid, lookup_group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_db() {
+        let db = parse_db(
+            "# comment\n\
+             root:x:0:0:root:/root:/bin/bash\n\
+             alice:x:1000:1000::/home/alice:/bin/sh\n\
+             toor:x:0:0::/root:/bin/sh\n\
+             broken\n\
+             wheel:x:10:root,alice\n",
+        );
+
+        assert_eq!(db.get(&0), Some(&Some("root".into())));
+        assert_eq!(db.get(&1000), Some(&Some("alice".into())));
+        assert_eq!(db.get(&10), Some(&Some("wheel".into())));
+        assert_eq!(db.len(), 3);
+    }
+
+    #[test]
+    fn test_user_name() {
+        assert_eq!(user_name(0).as_deref(), Some("root"));
+        assert_eq!(group_name(u32::MAX - 1), None);
+    }
+}
@@ -0,0 +1,484 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+const ACL_ACCESS: &str = "system.posix_acl_access";
+const ACL_DEFAULT: &str = "system.posix_acl_default";
+const CAPABILITY: &str = "security.capability";
+
+const CAPABILITY_NAMES: [&str; 41] = [
+    "chown",
+    "dac_override",
+    "dac_read_search",
+    "fowner",
+    "fsetid",
+    "kill",
+    "setgid",
+    "setuid",
+    "setpcap",
+    "linux_immutable",
+    "net_bind_service",
+    "net_broadcast",
+    "net_admin",
+    "net_raw",
+    "ipc_lock",
+    "ipc_owner",
+    "sys_module",
+    "sys_rawio",
+    "sys_chroot",
+    "sys_ptrace",
+    "sys_pacct",
+    "sys_admin",
+    "sys_boot",
+    "sys_nice",
+    "sys_resource",
+    "sys_time",
+    "sys_tty_config",
+    "mknod",
+    "lease",
+    "audit_write",
+    "audit_control",
+    "setfcap",
+    "mac_override",
+    "mac_admin",
+    "syslog",
+    "wake_alarm",
+    "block_suspend",
+    "audit_read",
+    "perfmon",
+    "bpf",
+    "checkpoint_restore",
+];
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use libc::{c_char, c_void, size_t, ssize_t};
+
+    pub unsafe fn list(path: *const c_char, buf: *mut c_char, size: size_t) -> ssize_t {
+        libc::llistxattr(path, buf, size)
+    }
+
+    pub unsafe fn get(
+        path: *const c_char,
+        name: *const c_char,
+        buf: *mut c_void,
+        size: size_t,
+    ) -> ssize_t {
+        libc::lgetxattr(path, name, buf, size)
+    }
+
+    pub unsafe fn set(
+        path: *const c_char,
+        name: *const c_char,
+        value: *const c_void,
+        size: size_t,
+    ) -> i32 {
+        libc::lsetxattr(path, name, value, size, 0)
+    }
+
+    pub unsafe fn remove(path: *const c_char, name: *const c_char) -> i32 {
+        libc::lremovexattr(path, name)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+    use libc::{c_char, c_void, size_t, ssize_t, XATTR_NOFOLLOW};
+
+    pub unsafe fn list(path: *const c_char, buf: *mut c_char, size: size_t) -> ssize_t {
+        libc::listxattr(path, buf, size, XATTR_NOFOLLOW)
+    }
+
+    pub unsafe fn get(
+        path: *const c_char,
+        name: *const c_char,
+        buf: *mut c_void,
+        size: size_t,
+    ) -> ssize_t {
+        libc::getxattr(path, name, buf, size, 0, XATTR_NOFOLLOW)
+    }
+
+    pub unsafe fn set(
+        path: *const c_char,
+        name: *const c_char,
+        value: *const c_void,
+        size: size_t,
+    ) -> i32 {
+        libc::setxattr(path, name, value, size, 0, XATTR_NOFOLLOW)
+    }
+
+    pub unsafe fn remove(path: *const c_char, name: *const c_char) -> i32 {
+        libc::removexattr(path, name, XATTR_NOFOLLOW)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod sys {
+    use libc::{c_char, c_void, size_t, ssize_t};
+
+    pub unsafe fn list(_: *const c_char, _: *mut c_char, _: size_t) -> ssize_t {
+        -1
+    }
+
+    pub unsafe fn get(
+        _: *const c_char,
+        _: *const c_char,
+        _: *mut c_void,
+        _: size_t,
+    ) -> ssize_t {
+        -1
+    }
+
+    pub unsafe fn set(
+        _: *const c_char,
+        _: *const c_char,
+        _: *const c_void,
+        _: size_t,
+    ) -> i32 {
+        -1
+    }
+
+    pub unsafe fn remove(_: *const c_char, _: *const c_char) -> i32 {
+        -1
+    }
+}
+
+fn ensure_supported() -> io::Result<()> {
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        Ok(())
+    } else {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+fn to_cstring(bytes: &[u8]) -> io::Result<CString> {
+    CString::new(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// List the extended attribute names of the path, without following symlinks.
+pub fn list(path: &Path) -> io::Result<Vec<String>> {
+    ensure_supported()?;
+    let path = to_cstring(path.as_os_str().as_bytes())?;
+
+    let size = unsafe { sys::list(path.as_ptr(), std::ptr::null_mut(), 0) };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0_u8; size as usize];
+    let size = unsafe { sys::list(path.as_ptr(), buf.as_mut_ptr().cast(), buf.len()) };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(size as usize);
+
+    Ok(buf
+        .split(|b| *b == 0)
+        .filter(|n| !n.is_empty())
+        .map(|n| String::from_utf8_lossy(n).to_string())
+        .collect())
+}
+
+/// Get the value of an extended attribute, without following symlinks.
+pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+    ensure_supported()?;
+    let path = to_cstring(path.as_os_str().as_bytes())?;
+    let name = to_cstring(name.as_bytes())?;
+
+    let size =
+        unsafe { sys::get(path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0_u8; size as usize];
+    let size = unsafe {
+        sys::get(
+            path.as_ptr(),
+            name.as_ptr(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+        )
+    };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(size as usize);
+
+    Ok(buf)
+}
+
+/// Set the value of an extended attribute, without following symlinks.
+pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    ensure_supported()?;
+    let path = to_cstring(path.as_os_str().as_bytes())?;
+    let name = to_cstring(name.as_bytes())?;
+
+    let res = unsafe {
+        sys::set(
+            path.as_ptr(),
+            name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+        )
+    };
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Remove an extended attribute, without following symlinks.
+pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+    ensure_supported()?;
+    let path = to_cstring(path.as_os_str().as_bytes())?;
+    let name = to_cstring(name.as_bytes())?;
+
+    let res = unsafe { sys::remove(path.as_ptr(), name.as_ptr()) };
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Textual values are displayed as is, binary values as hex.
+fn display_value(value: &[u8]) -> String {
+    let text = value.strip_suffix(&[0]).unwrap_or(value);
+    match std::str::from_utf8(text) {
+        Ok(s) if !s.chars().any(char::is_control) => s.to_string(),
+        _ => {
+            let hex: String = value.iter().map(|b| format!("{b:02x}")).collect();
+            format!("0x{hex}")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct AclEntry {
+    /// One of `user`, `group`, `mask` or `other`.
+    pub tag: String,
+
+    /// The user or group ID. Empty for the owner, the owning group, the mask
+    /// and others.
+    pub id: Option<u32>,
+
+    /// For e.g. `rw-`.
+    pub permissions: String,
+
+    /// Whether it's a default ACL entry of a directory.
+    pub is_default: bool,
+}
+
+/// Parse the value of `system.posix_acl_access` or `system.posix_acl_default`.
+fn parse_acl(value: &[u8], is_default: bool) -> Vec<AclEntry> {
+    // The header is just the version number.
+    let Some(entries) = value.get(4..) else {
+        return vec![];
+    };
+
+    entries
+        .chunks_exact(8)
+        .filter_map(|e| {
+            let tag = u16::from_le_bytes([e[0], e[1]]);
+            let perm = u16::from_le_bytes([e[2], e[3]]);
+            let id = u32::from_le_bytes([e[4], e[5], e[6], e[7]]);
+
+            let (tag, id) = match tag {
+                0x01 => ("user", None),
+                0x02 => ("user", Some(id)),
+                0x04 => ("group", None),
+                0x08 => ("group", Some(id)),
+                0x10 => ("mask", None),
+                0x20 => ("other", None),
+                _ => return None,
+            };
+
+            let permissions = [(4, 'r'), (2, 'w'), (1, 'x')]
+                .iter()
+                .map(|(bit, c)| if perm & bit != 0 { *c } else { '-' })
+                .collect();
+
+            Some(AclEntry {
+                tag: tag.into(),
+                id,
+                permissions,
+                is_default,
+            })
+        })
+        .collect()
+}
+
+/// Parse the value of `security.capability` into the `getcap` format, for e.g.
+/// `cap_net_bind_service,cap_net_raw=ep`.
+fn parse_capabilities(value: &[u8]) -> Option<String> {
+    let word = |i: usize| -> Option<u32> {
+        let b = value.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let magic = word(0)?;
+    let is_effective = magic & 1 != 0;
+    let (permitted, inheritable) = match magic & 0xff00_0000 {
+        0x0100_0000 => (word(1)? as u64, word(2)? as u64),
+        0x0200_0000 | 0x0300_0000 => (
+            word(1)? as u64 | (word(3)? as u64) << 32,
+            word(2)? as u64 | (word(4)? as u64) << 32,
+        ),
+        _ => return None,
+    };
+
+    let mut groups: Vec<(String, Vec<String>)> = vec![];
+    for i in 0..64 {
+        let is_permitted = permitted & (1 << i) != 0;
+        let is_inheritable = inheritable & (1 << i) != 0;
+        if !is_permitted && !is_inheritable {
+            continue;
+        }
+
+        let mut flags = String::new();
+        if is_effective {
+            flags.push('e');
+        }
+        if is_inheritable {
+            flags.push('i');
+        }
+        if is_permitted {
+            flags.push('p');
+        }
+
+        let name = CAPABILITY_NAMES
+            .get(i)
+            .map(|n| format!("cap_{n}"))
+            .unwrap_or_else(|| format!("cap_{i}"));
+
+        if let Some((_, names)) = groups.iter_mut().find(|(f, _)| f == &flags) {
+            names.push(name);
+        } else {
+            groups.push((flags, vec![name]));
+        }
+    }
+
+    Some(
+        groups
+            .into_iter()
+            .map(|(flags, names)| format!("{0}={flags}", names.join(",")))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// The extended attributes, POSIX ACL entries and file capabilities of a node.
+#[derive(Debug, Clone, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedMetadata {
+    pub xattrs: BTreeMap<String, String>,
+    pub acl: Vec<AclEntry>,
+    pub capabilities: Option<String>,
+}
+
+impl ExtendedMetadata {
+    pub fn load(path: &Path) -> Self {
+        let mut meta = Self::default();
+
+        // Unsupported file-systems simply don't have any extended attribute.
+        for name in list(path).unwrap_or_default() {
+            let Ok(value) = get(path, &name) else {
+                continue;
+            };
+
+            match name.as_str() {
+                ACL_ACCESS => meta.acl.extend(parse_acl(&value, false)),
+                ACL_DEFAULT => meta.acl.extend(parse_acl(&value, true)),
+                CAPABILITY => meta.capabilities = parse_capabilities(&value),
+                _ => {}
+            }
+
+            meta.xattrs.insert(name, display_value(&value));
+        }
+
+        meta
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Xattr {
+    pub name: String,
+
+    #[serde(default)]
+    pub value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_acl() {
+        let mut value = 2_u32.to_le_bytes().to_vec();
+        for (tag, perm, id) in [
+            (0x01_u16, 6_u16, u32::MAX),
+            (0x02, 4, 1000),
+            (0x04, 4, u32::MAX),
+            (0x10, 5, u32::MAX),
+            (0x20, 0, u32::MAX),
+        ] {
+            value.extend(tag.to_le_bytes());
+            value.extend(perm.to_le_bytes());
+            value.extend(id.to_le_bytes());
+        }
+
+        let acl = parse_acl(&value, false)
+            .into_iter()
+            .map(|e| (e.tag, e.id, e.permissions))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            acl,
+            vec![
+                ("user".into(), None, "rw-".into()),
+                ("user".into(), Some(1000), "r--".into()),
+                ("group".into(), None, "r--".into()),
+                ("mask".into(), None, "r-x".into()),
+                ("other".into(), None, "---".into()),
+            ]
+        );
+
+        assert!(parse_acl(&[], true).is_empty());
+    }
+
+    #[test]
+    fn test_parse_capabilities() {
+        let value = [0x0200_0001_u32, 1 << 10 | 1 << 13, 0, 0, 0]
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            parse_capabilities(&value),
+            Some("cap_net_bind_service,cap_net_raw=ep".into())
+        );
+
+        let value = [0x0200_0000_u32, 1, 1 << 1, 1 << 9, 0]
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            parse_capabilities(&value),
+            Some("cap_chown,cap_41=p cap_dac_override=i".into())
+        );
+
+        assert_eq!(parse_capabilities(&[1, 2]), None);
+    }
+
+    #[test]
+    fn test_display_value() {
+        assert_eq!(display_value(b"hello\0"), "hello");
+        assert_eq!(display_value(&[0, 1, 255]), "0x0001ff");
+    }
+}
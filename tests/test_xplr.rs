@@ -56,6 +56,76 @@ fn test_cli_help() {
         .stderr("");
 }
 
+#[test]
+fn test_cli_headless() {
+    let out = Command::new(cargo_bin!())
+        .arg("--headless")
+        .arg("src/lib.rs")
+        .write_stdin("")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("/src/lib.rs\n"));
+
+    let out = Command::new(cargo_bin!())
+        .arg("--headless")
+        .arg("src/lib.rs")
+        .write_stdin(
+            "ToggleSelection\nFocusNext\nToggleSelection\nPrintSelectionAndQuit\nQuit\n",
+        )
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout).lines().count(), 2);
+
+    let out = Command::new(cargo_bin!())
+        .arg("--headless")
+        .arg("--on-load")
+        .arg("ChangeDirectory: src")
+        .write_stdin("PrintPwdAndQuit\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("/src\n"));
+
+    let out = Command::new(cargo_bin!())
+        .arg("--headless")
+        .arg("--output-format")
+        .arg("jsonl")
+        .arg("src/lib.rs")
+        .write_stdin("PrintResultAndQuit\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with('{'));
+    assert!(stdout.contains("/src/lib.rs"));
+
+    // The logged errors are printed, and fail the run.
+    let out = Command::new(cargo_bin!())
+        .arg("--headless")
+        .arg("--read-only")
+        .arg("/tmp")
+        .write_stdin("ChangeDirectory: /nonexistent\nBashExec: \"true\"\n")
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(
+        stderr.lines().filter(|l| l.starts_with("error: ")).count(),
+        2
+    );
+    assert!(stderr.contains("/nonexistent"));
+
+    Command::new(cargo_bin!())
+        .arg("--headless")
+        .write_stdin("NoSuchMessage\n")
+        .assert()
+        .failure()
+        .code(1);
+}
+
 // TODO fix GitHub CI failures
 //
 // #[test]